@@ -0,0 +1,38 @@
+use std::time::Instant;
+
+const RUNS: usize = 5;
+
+// cargo bench --bench perft                             (magic bitboards)
+// cargo bench --bench perft --features raycast-sliders  (ray walking sliders, as before the magics)
+fn main() {
+  let sliders = if cfg!(feature = "raycast-sliders") { "raycast" } else { "magic" };
+  println!("slider attacks: {}", sliders);
+
+  let positions: [(&str, &str, u32); 4] = [
+    ("start position", "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 5),
+    ("kiwipete", "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 4),
+    ("middlegame", "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10", 4),
+    ("sliders", "R6R/3Q4/1Q4Q1/4Q3/2Q4Q/Q4Q2/pp1Q4/kBNN1KB1 w - - 0 1", 4)
+  ];
+
+  // builds the lazily initialised attack tables before anything is timed
+  engine::perft(positions[0].1, 1).unwrap();
+
+  let mut total_nodes = 0u64;
+  let mut total_seconds = 0f64;
+  for (name, fen, depth) in positions {
+    // best of a few runs, to keep scheduler noise out of the numbers
+    let mut nodes = 0u64;
+    let mut seconds = f64::MAX;
+    for _ in 0..RUNS {
+      let start = Instant::now();
+      nodes = engine::perft(fen, depth).unwrap();
+      seconds = seconds.min(start.elapsed().as_secs_f64());
+    }
+
+    println!("{:<16} depth {} {:>10} nodes {:>8.3} s {:>12.0} nps", name, depth, nodes, seconds, nodes as f64 / seconds);
+    total_nodes += nodes;
+    total_seconds += seconds;
+  }
+  println!("{:<16}         {:>10} nodes {:>8.3} s {:>12.0} nps", "total", total_nodes, total_seconds, total_nodes as f64 / total_seconds);
+}
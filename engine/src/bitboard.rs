@@ -2,10 +2,17 @@ mod attackmaps;
 mod utils;
 mod legality;
 mod checkinfo;
+mod undoinfo;
 mod attacks;
 mod movegen;
+mod draws;
+mod perft;
+mod eval;
+mod san;
+mod zobrist;
 
 pub mod board;
 pub(in super) mod bitmove;
 pub(in super) mod movebuffer;
+pub(in super) mod legalmoves;
 pub(in super) mod makemove;
\ No newline at end of file
@@ -1,5 +1,7 @@
 use once_cell::sync::Lazy;
 
+use super::attacks::get_raycast_from_square_in_direction;
+
 const A_FILE: u64 = 0x0101_0101_0101_0101;
 const H_FILE: u64 = 0x8080_8080_8080_8080;
 const AB_FILE: u64 = 0x0303_0303_0303_0303;
@@ -153,6 +155,159 @@ pub static KING_SAFETY_BISHOP_MASK: Lazy<[u64; 64]> = Lazy::new(|| {
 });
 
 
+// SQUARES_BETWEEN[<square_index>][<square_index>], empty if the squares do not share a line
+pub static SQUARES_BETWEEN: Lazy<[[u64; 64]; 64]> = Lazy::new(|| {
+  let mut table = [[0u64; 64]; 64];
+
+  for sq in 0..64 {
+    for dir in 0..8 {
+      let mut ray = RAY_TABLE[sq][dir];
+
+      while ray != 0 {
+        let target_sq = ray.trailing_zeros() as usize;
+        table[sq][target_sq] = RAY_TABLE[sq][dir] & !RAY_TABLE[target_sq][dir] & !(1 << target_sq);
+        ray &= !(1 << target_sq);
+      }
+    }
+  }
+  table
+});
+
+#[derive(Copy, Clone)]
+pub struct Magic {
+  pub mask: u64,
+  pub magic: u64,
+  pub shift: u32,
+  pub offset: usize
+}
+
+pub struct MagicTable {
+  pub magics: [Magic; 64],
+  pub attacks: Vec<u64>,
+  #[cfg(feature = "raycast-sliders")]
+  pub dirs: [usize; 4]
+}
+
+impl MagicTable {
+  #[inline(always)]
+  pub fn get_attacks(&self, sq: usize, occupancy: u64) -> u64 {
+    // the ray walking lookup the magics replaced, kept so the bench can compare the two
+    #[cfg(feature = "raycast-sliders")]
+    {
+      return get_raycast_attacks(sq, occupancy, self.dirs);
+    }
+    #[cfg(not(feature = "raycast-sliders"))]
+    {
+      let magic = &self.magics[sq];
+      let index = ((occupancy & magic.mask).wrapping_mul(magic.magic) >> magic.shift) as usize;
+      return self.attacks[magic.offset + index];
+    }
+  }
+}
+
+// magic numbers found with a sparse random search, any number that maps every relevant occupancy
+// of the square to a slot without destructive collisions works
+const ROOK_MAGIC_NUMBERS: [u64; 64] = [
+  0x0180_0021_80C0_0030, 0x0840_0040_1000_2008, 0x3080_2000_1000_0880, 0x8280_0800_8010_0004,
+  0x1080_0400_0800_0281, 0x0100_0400_0100_0802, 0x0A00_4200_0401_0088, 0x0480_0500_02A0_4180,
+  0x4402_8000_4001_6280, 0x0010_8040_0820_0080, 0x0000_8080_2000_1000, 0x0302_8008_0010_0080,
+  0x0010_8080_0800_0400, 0x200A_0030_0200_0804, 0x04A9_0001_00C4_0200, 0x0002_0012_08A0_4401,
+  0x0400_8480_0540_0130, 0x8011_0200_2880_4200, 0x8020_0100_1100_4020, 0x4098_2200_0A02_4010,
+  0x0000_8080_0400_0800, 0x2208_8080_0200_0401, 0x0019_8400_8108_0210, 0x4005_0200_0044_0091,
+  0x0402_4006_8008_8026, 0x0010_0041_4000_2010, 0x8018_8202_0040_1020, 0x0080_0901_0010_0420,
+  0x0406_1801_8004_0180, 0x4850_0200_8004_0080, 0x0210_1004_0002_0801, 0x2283_0001_0000_6082,
+  0x3040_8040_0280_0022, 0x0001_8840_0680_2000, 0x0040_1000_8080_2002, 0x1000_8010_0480_0801,
+  0x2000_0408_0100_1100, 0x0008_8004_0080_0200, 0x0040_0842_0400_0110, 0x100E_0080_4200_2104,
+  0x3080_0040_2000_4002, 0x3100_2010_0042_4000, 0x2020_0040_2901_0010, 0x400E_4200_120A_0020,
+  0x2001_0801_0501_0010, 0x0006_0010_0402_0008, 0x0004_7002_0114_0008, 0x0050_0080_4402_0001,
+  0x4000_8314_4022_0200, 0x0220_0030_0040_0040, 0x0000_2002_4500_1100, 0x0D0C_8008_0010_0080,
+  0x0100_8800_800C_0180, 0x0004_0002_0041_0040, 0x0A10_A209_3008_9400, 0x8100_1420_4081_0200,
+  0x0400_5128_8000_4501, 0x0040_0081_0C20_1041, 0x0000_4082_0008_1022, 0x6008_0C21_8850_0101,
+  0x0612_0020_0408_1002, 0x1102_0004_0810_0182, 0x2000_8100_8208_1004, 0x0081_0000_2050_8201
+];
+
+const BISHOP_MAGIC_NUMBERS: [u64; 64] = [
+  0x0488_0248_0800_2080, 0x9810_0441_0062_011A, 0x0A10_0410_8030_0001, 0x8108_0485_1040_A000,
+  0x2401_1040_2800_2001, 0x2848_2848_0822_4807, 0x0012_0811_0450_0600, 0x0003_0108_2082_0800,
+  0x0040_1004_8144_0410, 0x8288_08B0_0448_82A0, 0x381A_1004_2684_2000, 0x1104_2804_8114_0504,
+  0x0000_0110_4002_1008, 0x0800_4202_1104_2004, 0x2002_0201_0160_9002, 0x2001_0041_0808_0200,
+  0x40A0_3090_8202_0830, 0x0244_04E0_2800_810E, 0x0028_0010_5801_4050, 0x2404_0208_4013_2000,
+  0x0826_0004_2201_0000, 0x0000_402E_010C_A000, 0x1010_6002_0202_6004, 0x9002_4801_0448_0408,
+  0x0022_2044_B0E0_0250, 0x1010_1A0C_2888_0302, 0x0828_0814_0440_4300, 0x0008_0800_4020_2020,
+  0x2001_0010_0500_4000, 0x0081_0040_0200_5000, 0x2021_0100_9200_D040, 0x0004_0080_0822_0126,
+  0x1610_4208_0020_0808, 0x8242_0211_3042_3006, 0x3400_2101_0010_0400, 0x82A8_0200_8008_0080,
+  0x0008_1002_400C_0104, 0x8002_0042_0409_0080, 0x0008_0CC2_8009_0808, 0x705C_8C09_0090_808C,
+  0x2064_0120_1000_8902, 0x1428_4230_102B_8480, 0x0006_0500_4800_6500, 0x9180_3104_1100_8805,
+  0x0020_2002_0080_2410, 0x0004_0082_0200_0410, 0x4008_2848_0080_0840, 0x0044_1882_0050_0204,
+  0x4002_0801_0510_0000, 0x9002_0901_0150_4000, 0x0201_1280_8441_1004, 0x8200_8880_8404_0002,
+  0x0010_4040_8822_0100, 0x4400_4128_0109_0280, 0x0004_8404_8802_0000, 0x0002_0274_0400_8100,
+  0x0041_0108_0202_0200, 0x0000_3022_0210_0500, 0x2000_2008_4200_9000, 0x8000_0140_0442_0209,
+  0x8150_0010_2425_0C00, 0x8042_0010_A081_0C41, 0x0106_8802_1002_2200, 0x01C0_0404_0050_4102
+];
+
+// ROOK_MAGICS.get_attacks(<square_index>, <occupancy>)
+pub static ROOK_MAGICS: Lazy<MagicTable> = Lazy::new(|| {
+  return build_magic_table(&ROOK_MAGIC_NUMBERS, [0, 2, 4, 6]);
+});
+
+// BISHOP_MAGICS.get_attacks(<square_index>, <occupancy>)
+pub static BISHOP_MAGICS: Lazy<MagicTable> = Lazy::new(|| {
+  return build_magic_table(&BISHOP_MAGIC_NUMBERS, [1, 3, 5, 7]);
+});
+
+// the relevant occupancy of a slider: every square on its rays except the last one,
+// since a piece on the edge of the board can not block anything behind it
+fn get_relevant_occupancy_mask(sq: usize, dirs: [usize; 4]) -> u64 {
+  let mut mask = 0u64;
+
+  for dir in dirs {
+    let ray = RAY_TABLE[sq][dir];
+    if ray == 0 { continue; }
+
+    let last_sq = if dir / 4 == 0 { 63 - ray.leading_zeros() } else { ray.trailing_zeros() };
+    mask |= ray & !(1 << last_sq);
+  }
+  mask
+}
+
+fn get_raycast_attacks(sq: usize, occupancy: u64, dirs: [usize; 4]) -> u64 {
+  let mut attacks = 0u64;
+
+  for dir in dirs {
+    attacks |= get_raycast_from_square_in_direction(occupancy, sq, dir);
+  }
+  attacks
+}
+
+fn build_magic_table(magic_numbers: &[u64; 64], dirs: [usize; 4]) -> MagicTable {
+  let mut table = MagicTable {
+    magics: [Magic { mask: 0, magic: 0, shift: 0, offset: 0 }; 64],
+    attacks: Vec::new(),
+    #[cfg(feature = "raycast-sliders")]
+    dirs
+  };
+
+  for sq in 0..64 {
+    let mask = get_relevant_occupancy_mask(sq, dirs);
+    let magic = magic_numbers[sq];
+    let shift = 64 - mask.count_ones();
+    let offset = table.attacks.len();
+    table.attacks.resize(offset + (1 << mask.count_ones()), 0u64);
+
+    // every subset of the mask (carry-rippler)
+    let mut occupancy = 0u64;
+    loop {
+      let index = (occupancy.wrapping_mul(magic) >> shift) as usize;
+      table.attacks[offset + index] = get_raycast_attacks(sq, occupancy, dirs);
+
+      occupancy = occupancy.wrapping_sub(mask) & mask;
+      if occupancy == 0 { break; }
+    }
+    table.magics[sq] = Magic { mask, magic, shift, offset };
+  }
+  table
+}
+
 
 // <----- TESTS ----->
 
@@ -307,4 +462,41 @@ mod tests {
       assert_eq!(RAY_TABLE[starting_square_index][direction], ray_masks[direction]);
     }
   }
-}
\ No newline at end of file
+
+  #[test]
+  fn test_squares_between() {
+    // a1 - h8 diagonal
+    assert_eq!(SQUARES_BETWEEN[0][63], (1 << 9) | (1 << 18) | (1 << 27) | (1 << 36) | (1 << 45) | (1 << 54));
+    assert_eq!(SQUARES_BETWEEN[63][0], SQUARES_BETWEEN[0][63]);
+
+    // e1 - e4 file
+    assert_eq!(SQUARES_BETWEEN[4][28], (1 << 12) | (1 << 20));
+
+    // neighbours and squares not sharing a line
+    assert_eq!(SQUARES_BETWEEN[27][28], 0);
+    assert_eq!(SQUARES_BETWEEN[0][17], 0);
+  }
+
+  #[test]
+  fn test_magic_attacks_match_raycasts() {
+    let mut state: u64 = 0x1234_5678_9ABC_DEF0;
+    let mut next_random = || {
+      state ^= state >> 12;
+      state ^= state << 25;
+      state ^= state >> 27;
+      return state.wrapping_mul(0x2545_F491_4F6C_DD1D);
+    };
+
+    for sq in 0..64 {
+      for _ in 0..1000 {
+        // mix sparse and dense boards
+        let occupancy = next_random() & next_random() | if next_random() % 4 == 0 { next_random() } else { 0 };
+
+        assert_eq!(ROOK_MAGICS.get_attacks(sq, occupancy), get_raycast_attacks(sq, occupancy, [0, 2, 4, 6]), "rook attacks differ on square {} with occupancy {:#018x}", sq, occupancy);
+        assert_eq!(BISHOP_MAGICS.get_attacks(sq, occupancy), get_raycast_attacks(sq, occupancy, [1, 3, 5, 7]), "bishop attacks differ on square {} with occupancy {:#018x}", sq, occupancy);
+      }
+      assert_eq!(ROOK_MAGICS.get_attacks(sq, 0), ROOK_MOVE_MASK[sq]);
+      assert_eq!(BISHOP_MAGICS.get_attacks(sq, 0), BISHOP_MOVE_MASK[sq]);
+    }
+  }
+}
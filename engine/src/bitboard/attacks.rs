@@ -47,27 +47,11 @@ impl Board {
   }
   #[inline]
   pub fn get_pseudo_bishop_moves(&self, sq: u32) -> u64 {
-    let mut  moves = 0u64;
-    let sq  = sq as usize;
-    let occupancy = self.occupancy[2];
-    moves |= get_raycast_from_square_in_direction(occupancy, sq, 1);
-    moves |= get_raycast_from_square_in_direction(occupancy, sq, 3);
-    moves |= get_raycast_from_square_in_direction(occupancy, sq, 5);
-    moves |= get_raycast_from_square_in_direction(occupancy, sq, 7);
-
-    return moves;
+    return BISHOP_MAGICS.get_attacks(sq as usize, self.occupancy[2]);
   }
   #[inline]
   pub fn get_pseudo_rook_moves(&self, sq: u32) -> u64 {
-    let mut moves: u64 = 0u64;
-    let occupancy = self.occupancy[2];
-    let sq = sq as usize;
-    moves |= get_raycast_from_square_in_direction(occupancy, sq, 0);
-    moves |= get_raycast_from_square_in_direction(occupancy, sq, 2);
-    moves |= get_raycast_from_square_in_direction(occupancy, sq, 4);
-    moves |= get_raycast_from_square_in_direction(occupancy, sq, 6);
-
-    return moves;
+    return ROOK_MAGICS.get_attacks(sq as usize, self.occupancy[2]);
   }
   #[inline(always)]
   pub fn get_pseudo_queen_moves(&self, sq: u32) -> u64 {
@@ -76,52 +60,42 @@ impl Board {
 
   #[inline]
   pub fn get_pseudo_bishop_moves_ignore_king(&self, sq: u32) -> u64 {
-    let mut  moves = 0u64;
-    let sq  = sq as usize;
     let king = self.bitboards[5 + 6*self.side_to_move as usize];
-    let occupancy = self.occupancy[2] & !king;
-    moves |= get_raycast_from_square_in_direction(occupancy, sq, 1);
-    moves |= get_raycast_from_square_in_direction(occupancy, sq, 3);
-    moves |= get_raycast_from_square_in_direction(occupancy, sq, 5);
-    moves |= get_raycast_from_square_in_direction(occupancy, sq, 7);
-
-    return moves;
+    return BISHOP_MAGICS.get_attacks(sq as usize, self.occupancy[2] & !king);
   }
   #[inline]
   pub fn get_pseudo_rook_moves_ignore_king(&self, sq: u32) -> u64 {
-    let mut moves: u64 = 0u64;
-    let sq = sq as usize;
     let king = self.bitboards[5 + 6*self.side_to_move as usize];
-    let occupancy = self.occupancy[2] & !king;
-    moves |= get_raycast_from_square_in_direction(occupancy, sq, 0);
-    moves |= get_raycast_from_square_in_direction(occupancy, sq, 2);
-    moves |= get_raycast_from_square_in_direction(occupancy, sq, 4);
-    moves |= get_raycast_from_square_in_direction(occupancy, sq, 6);
-
-    return moves;
+    return ROOK_MAGICS.get_attacks(sq as usize, self.occupancy[2] & !king);
   }
 
+  // sliders see through the squares missing from occupancy, the opponent king counts as an attacker too
   #[inline]
-  pub fn is_square_attacked(&self, king_sq: u32) -> bool {
+  pub fn is_square_attacked(&self, sq: u32, occupancy: u64) -> bool {
     let offset: usize = 6 * self.side_to_move as usize;
 
-    // rook-queen checks (+)
-    let mut threat_mask: u64 = self.get_pseudo_rook_moves(king_sq);
+    // rook-queen attacks (+)
+    let mut threat_mask: u64 = ROOK_MAGICS.get_attacks(sq as usize, occupancy);
     let mut attacker_mask: u64 = self.bitboards[10 - offset] | self.bitboards[9 - offset];
     if threat_mask & attacker_mask != 0 { return true; }
 
-    // bishop-queen checks (x)
-    threat_mask = self.get_pseudo_bishop_moves(king_sq);
+    // bishop-queen attacks (x)
+    threat_mask = BISHOP_MAGICS.get_attacks(sq as usize, occupancy);
     attacker_mask = self.bitboards[10 - offset] | self.bitboards[8 - offset];
     if threat_mask & attacker_mask != 0 { return true; }
 
-    // knight checks (L)
-    threat_mask = KNIGHT_ATTACK_MAP[king_sq as usize];
+    // knight attacks (L)
+    threat_mask = KNIGHT_ATTACK_MAP[sq as usize];
     attacker_mask = self.bitboards[7 - offset];
     if threat_mask & attacker_mask != 0 { return true; }
 
-    // pawn checks (v)
-    threat_mask = PAWN_ATTACK_MAP[king_sq as usize][self.side_to_move as usize];
+    // king attacks (*)
+    threat_mask = KING_ATTACK_MAP[sq as usize];
+    attacker_mask = self.bitboards[11 - offset];
+    if threat_mask & attacker_mask != 0 { return true; }
+
+    // pawn attacks (v)
+    threat_mask = PAWN_ATTACK_MAP[sq as usize][self.side_to_move as usize];
     attacker_mask = self.bitboards[6 - offset];
     return threat_mask & attacker_mask != 0;
   }
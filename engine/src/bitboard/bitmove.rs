@@ -1,6 +1,6 @@
 use super::utils::*;
 
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub struct BitMove {
 
   move_type: BitMoveType,
@@ -38,6 +38,15 @@ impl BitMove {
       promotion_piece: None
     };
   }
+  #[inline]
+  pub fn en_passant(from: u8, to: u8) -> Self {
+    return Self {
+      move_type: BitMoveType::EnPassant,
+      from_square: from,
+      to_square: to,
+      promotion_piece: None
+    };
+  }
 
   #[inline(always)]
   pub fn move_type(&self) -> BitMoveType {
@@ -68,7 +77,7 @@ impl BitMove {
   }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum BitMoveType {
   Quiet,
   Capture,
@@ -1,16 +1,24 @@
 use crate::bitboard::utils::notation_from_square_number;
 
 use super::utils::try_get_square_number_from_notation;
+use super::undoinfo::UndoInfo;
+use crate::fenerror::FenError;
 
+#[derive(Clone)]
 pub struct Board {
   pub(in super) bitboards: [u64; 12],     // 0-5 -> white pieces (P, N, B, R, Q, K), 6-11 -> black pieces (p, n, b, r, q, k)
   pub(in super) piece_board: [u8; 64],    // same as board indexes, 12 -> empty square
   pub(in super) occupancy: [u64; 3],      // 0 -> white, 1 -> black, 2 -> combined
   pub(in super) castling_rights: u8,      // 0b0000_KQkq
+  pub(in super) castling_rooks: [u8; 4],  // starting square of the rook for each castling right, same bit order as castling_rights
   pub(in super) pinned_squares: [u8; 64], // 0 -> E-W, 1 -> NE-SW, 2 -> N-S, 3 -> SE-NW, 4 -> no pin
   pub(in super) pin_mask: u64,            // 1 -> pin, 0 -> no pin
   pub(in super) en_passant_square: u64,   // 1 -> ep square, 0 -> no ep square
-  pub(in super) side_to_move: u8          // 0 -> white to play, 1 -> black to play
+  pub(in super) side_to_move: u8,         // 0 -> white to play, 1 -> black to play
+  pub(in super) halfmove_clock: u16,      // plies since the last capture or pawn move
+  pub(in super) fullmove_number: u16,     // starts at 1, incremented after every black move
+  pub(in super) hash: u64,                // zobrist key of the position
+  pub(in super) undo_stack: Vec<UndoInfo> // one entry per move played with make_move
 }
 
 impl Board {
@@ -22,10 +30,15 @@ impl Board {
       piece_board: [12; 64],
       occupancy: [0x0000_0000_0000_0000; 3],
       castling_rights: 0b0000_0000,
+      castling_rooks: [56, 63, 0, 7],
       pinned_squares: [4; 64],
       pin_mask: 0u64,
       en_passant_square: 0x0000_0000_0000_0000,
-      side_to_move: 0
+      side_to_move: 0,
+      halfmove_clock: 0,
+      fullmove_number: 1,
+      hash: 0u64,
+      undo_stack: Vec::new()
     };
 
     return bit_board;
@@ -47,81 +60,186 @@ impl Board {
       piece_board: [12; 64],
       occupancy: [0; 3],
       castling_rights: 0b0000_1111,
+      castling_rooks: [56, 63, 0, 7],
       pinned_squares: [4; 64],
       pin_mask: 0u64,
       en_passant_square: 0x0000_0000_0000_0000,
-      side_to_move: 0
+      side_to_move: 0,
+      halfmove_clock: 0,
+      fullmove_number: 1,
+      hash: 0u64,
+      undo_stack: Vec::new()
     };
     bit_board.calc_occupancy();
     bit_board.calc_piece_board();
+    bit_board.hash = bit_board.calc_hash();
 
     return bit_board;
   }
   pub fn build(fen: &str) -> Self {
+    return Self::try_from_fen(fen).expect("invalid fen notation");
+  }
+  pub fn try_from_fen(fen: &str) -> Result<Self, FenError> {
     let mut board: Board = Board::new_clear();
+    let mut fields = fen.split_whitespace();
+
+    let placement = fields.next().ok_or(FenError::MissingField("piece placement".to_string()))?;
+    board.parse_piece_placement(placement)?;
+    board.calc_occupancy();
+    board.calc_piece_board();
+
+    let side = fields.next().ok_or(FenError::MissingField("side to move".to_string()))?;
+    match side {
+      "w" => board.side_to_move = 0,
+      "b" => board.side_to_move = 1,
+       _  => return Err(FenError::InvalidSideToMove(side.to_string()))
+    }
+
+    let castling = fields.next().ok_or(FenError::MissingField("castling".to_string()))?;
+    board.parse_castling_rights(castling)?;
+
+    let en_passant = fields.next().ok_or(FenError::MissingField("en passant".to_string()))?;
+    board.parse_en_passant_square(en_passant)?;
+
+    // the move counters are often left off, default to a fresh game in that case
+    if let Some(halfmove_clock) = fields.next() {
+      board.halfmove_clock = halfmove_clock.parse::<u16>()
+        .map_err(|_| FenError::InvalidHalfmoveClock(halfmove_clock.to_string()))?;
+    }
+    if let Some(fullmove_number) = fields.next() {
+      board.fullmove_number = match fullmove_number.parse::<u16>() {
+        Ok(number) if number > 0 => number,
+        _ => return Err(FenError::InvalidFullmoveNumber(fullmove_number.to_string()))
+      };
+    }
+
+    board.validate_kings()?;
+    board.calc_pinned_squares();
+    board.hash = board.calc_hash();
 
-    let mut col: i32 = 0;
-    let mut row: i32 = 7;
+    return Ok(board);
+  }
+
+  fn parse_piece_placement(&mut self, placement: &str) -> Result<(), FenError> {
     let pieces: [char; 12] = ['p', 'n', 'b', 'r', 'q', 'k', 'P', 'N', 'B', 'R', 'Q', 'K'];
-    let mut coming_up: &str = fen;
+    let ranks: Vec<&str> = placement.split('/').collect();
+    if ranks.len() != 8 {
+      return Err(FenError::InvalidRankCount(ranks.len()));
+    }
+
+    for (i, rank) in ranks.iter().enumerate() {
+      let row: usize = 7 - i;
+      let mut col: usize = 0;
 
-    for (i, c) in coming_up.chars().enumerate() {
-      if pieces.contains(&c) {
-        board.place_piece(row*8 + col, c);
-        col += 1;
+      for c in rank.chars() {
+        if pieces.contains(&c) {
+          if col >= 8 {
+            return Err(FenError::RankOverflow(row + 1));
+          }
+          self.place_piece((row * 8 + col) as i32, c);
+          col += 1;
+        }
+        else if ('1'..='8').contains(&c) {
+          col += c.to_digit(10).unwrap() as usize;
+          if col > 8 {
+            return Err(FenError::RankOverflow(row + 1));
+          }
+        }
+        else {
+          return Err(FenError::InvalidPiecePlacement(c));
+        }
       }
-      else if ('1'..='8').contains(&c) {
-        col += c.to_string().parse::<i32>().unwrap();
+      if col < 8 {
+        return Err(FenError::IncompleteRank(row + 1));
       }
-      else if c == '/' {
-        row -= 1;
-        col = 0;
+    }
+    return Ok(());
+  }
+  // accepts the usual KQkq, Shredder-FEN (rook files, e.g. HAha) and X-FEN (a mix of the two)
+  fn parse_castling_rights(&mut self, castling: &str) -> Result<(), FenError> {
+    if castling == "-" {
+      return Ok(());
+    }
+    let invalid = || FenError::InvalidCastling(castling.to_string());
+
+    for c in castling.chars() {
+      let side: u8 = if c.is_ascii_uppercase() { 0 } else { 1 };
+      let back_rank = 56 * side;
+      let king_sq = self.bitboards[5 + 6 * side as usize].trailing_zeros() as u8;
+      let own_rooks = self.bitboards[3 + 6 * side as usize] & 0xFF << back_rank;
+      if king_sq / 8 != side * 7 {
+        return Err(invalid());
       }
-      else {
-        coming_up = &coming_up[i+1..];
-        break;
+
+      let rook_sq = match c.to_ascii_lowercase() {
+        // the outermost rook on that side of the king
+        'k' => match own_rooks & u64::MAX << king_sq << 1 {
+          0 => return Err(invalid()),
+          rooks => 63 - rooks.leading_zeros() as u8
+        },
+        'q' => match own_rooks & ((1 << king_sq) - 1) {
+          0 => return Err(invalid()),
+          rooks => rooks.trailing_zeros() as u8
+        },
+        'a'..='h' => back_rank + (c.to_ascii_lowercase() as u8 - b'a'),
+        _ => return Err(invalid())
+      };
+      if own_rooks & 1 << rook_sq == 0 {
+        return Err(invalid());
       }
-    }
-    board.calc_occupancy();
-    
-    match coming_up.chars().next().unwrap() {
-      'w' => board.side_to_move = 0,
-      'b' => board.side_to_move = 1,
-       _  => panic!("invalid fen notation / to be handled later")
-    }
-    coming_up = &coming_up[2..];
-
-    for (i, c) in coming_up.chars().enumerate() {
-      match c {
-        'K' => board.castling_rights |= 1 << 3,
-        'Q' => board.castling_rights |= 1 << 2,
-        'k' => board.castling_rights |= 1 << 1,
-        'q' => board.castling_rights |= 1,
-        '-' => {
-          coming_up = &coming_up[i+2..];
-          break;
-        }
-         _  => {
-          coming_up = &coming_up[i+1..];
-          break;
-        }
+
+      let right: u8 = (if rook_sq > king_sq { 2 } else { 1 }) << (2 - 2 * side);
+      let right_idx = right.trailing_zeros() as usize;
+      if self.castling_rights & right != 0 {
+        return Err(invalid());
       }
+      self.castling_rights |= right;
+      self.castling_rooks[right_idx] = rook_sq;
     }
-    match coming_up.chars().next().unwrap() {
-      '-' => {
-          coming_up = &coming_up[1..];
-        }
-       _  => {
-          let notation = coming_up.split(' ').next().unwrap();
-          if let Ok(epsq_index) = try_get_square_number_from_notation(notation) {
-            board.en_passant_square = 1 << epsq_index;
-          }
-       }
+    return Ok(());
+  }
+  fn parse_en_passant_square(&mut self, en_passant: &str) -> Result<(), FenError> {
+    if en_passant == "-" {
+      return Ok(());
     }
-    board.calc_pinned_squares();
-    board.calc_piece_board();
 
-    return board;
+    let Ok(epsq_index) = try_get_square_number_from_notation(en_passant) else {
+      return Err(FenError::InvalidEnPassant(en_passant.to_string()));
+    };
+    let ep_rank = if self.side_to_move == 0 { 5 } else { 2 };
+    if epsq_index / 8 != ep_rank {
+      return Err(FenError::InvalidEnPassant(en_passant.to_string()));
+    }
+    let (pawn_sq, origin_sq, pawn) = if self.side_to_move == 0 {
+      (epsq_index - 8, epsq_index + 8, 6)
+    } else {
+      (epsq_index + 8, epsq_index - 8, 0)
+    };
+
+    // some writers record the ep square after every double push, even when no pawn could have
+    // made it; only keep it if the pushed pawn is in front and the squares it passed are empty
+    if self.bitboards[pawn] & 1 << pawn_sq != 0
+      && self.occupancy[2] & (1 << epsq_index | 1 << origin_sq) == 0 {
+      self.en_passant_square = 1 << epsq_index;
+    }
+    return Ok(());
+  }
+  fn validate_kings(&mut self) -> Result<(), FenError> {
+    for (king, color) in [(5, "white"), (11, "black")] {
+      match self.bitboards[king].count_ones() {
+        0 => return Err(FenError::MissingKing(color.to_string())),
+        1 => (),
+        _ => return Err(FenError::ExtraKings(color.to_string()))
+      }
+    }
+
+    self.side_to_move = 1 - self.side_to_move;
+    let opponent_in_check = self.check_test().check_count > 0;
+    self.side_to_move = 1 - self.side_to_move;
+    if opponent_in_check {
+      return Err(FenError::OpponentInCheck);
+    }
+    return Ok(());
   }
 
   #[inline(always)]
@@ -141,6 +259,10 @@ impl Board {
     return self.castling_rights;
   }
   #[inline(always)]
+  pub fn castling_rook(&self, right_idx: usize) -> u8 {
+    return self.castling_rooks[right_idx];
+  }
+  #[inline(always)]
   pub fn pinned_squares(&self, sq: usize) -> u8 {
     return self.pinned_squares[sq];
   }
@@ -156,6 +278,18 @@ impl Board {
   pub fn side_to_move(&self) -> u8 {
     return self.side_to_move;
   }
+  #[inline(always)]
+  pub fn halfmove_clock(&self) -> u16 {
+    return self.halfmove_clock;
+  }
+  #[inline(always)]
+  pub fn fullmove_number(&self) -> u16 {
+    return self.fullmove_number;
+  }
+  #[inline(always)]
+  pub fn hash(&self) -> u64 {
+    return self.hash;
+  }
 
   #[inline(always)]
   pub fn current_king_square(&self) -> u32 {
@@ -194,10 +328,7 @@ impl Board {
     if self.castling_rights() == 0 {
       fen.push('-');
     } else {
-      if self.castling_rights() & (1 << 3) != 0 { fen.push('K'); }
-      if self.castling_rights() & (1 << 2) != 0 { fen.push('Q'); }
-      if self.castling_rights() & (1 << 1) != 0 { fen.push('k'); }
-      if self.castling_rights() & (1 << 0) != 0 { fen.push('q'); }
+      fen.push_str(&self.castling_notation());
     }
 
     fen.push(' ');
@@ -208,11 +339,73 @@ impl Board {
       fen.push_str(&notation_from_square_number(sq as u8));
     }
 
-    fen.push_str(" 0 1");
+    fen.push(' ');
+    fen.push_str(&self.halfmove_clock().to_string());
+    fen.push(' ');
+    fen.push_str(&self.fullmove_number().to_string());
 
     return fen;
   }
 
+  // X-FEN: KQkq for the outermost rooks, the rook's file when another rook stands further out
+  fn castling_notation(&self) -> String {
+    let mut notation = String::new();
+
+    for right_idx in (0..4).rev() {
+      if self.castling_rights & 1 << right_idx == 0 {
+        continue;
+      }
+      let side = 1 - right_idx / 2;
+      let is_kingside = right_idx % 2 == 1;
+      let rook_sq = self.castling_rooks[right_idx] as u32;
+      let own_rooks = self.bitboards[3 + 6 * side] & 0xFF << (56 * side);
+      let outer_squares = if is_kingside { u64::MAX << rook_sq << 1 } else { (1u64 << rook_sq) - 1 };
+
+      let c = if own_rooks & outer_squares != 0 {
+        (b'a' + (rook_sq % 8) as u8) as char
+      } else if is_kingside {
+        'k'
+      } else {
+        'q'
+      };
+      notation.push(if side == 0 { c.to_ascii_uppercase() } else { c });
+    }
+    return notation;
+  }
+
+  // the starting position of Fischer Random Chess with the given Scharnagl number (0-959), 518 is the standard setup
+  pub fn new_chess960(index: u16) -> Option<Self> {
+    if index >= 960 {
+      return None;
+    }
+    // the five squares left for the knights after the bishops and the queen are placed
+    let knight_placements: [(usize, usize); 10] = [(0, 1), (0, 2), (0, 3), (0, 4), (1, 2), (1, 3), (1, 4), (2, 3), (2, 4), (3, 4)];
+    let mut back_rank: [Option<char>; 8] = [None; 8];
+    let mut n = index as usize;
+
+    back_rank[n % 4 * 2 + 1] = Some('B');
+    n /= 4;
+    back_rank[n % 4 * 2] = Some('B');
+    n /= 4;
+    let mut place_on_empty = |nth: usize, piece: char| {
+      let file = (0..8).filter(|&file| back_rank[file].is_none()).nth(nth).unwrap();
+      back_rank[file] = Some(piece);
+    };
+    place_on_empty(n % 6, 'Q');
+    n /= 6;
+    let (first_knight, second_knight) = knight_placements[n];
+    place_on_empty(second_knight, 'N');
+    place_on_empty(first_knight, 'N');
+    // rook, king, rook on the three squares that are left
+    place_on_empty(0, 'R');
+    place_on_empty(0, 'K');
+    place_on_empty(0, 'R');
+
+    let white: String = back_rank.iter().map(|piece| piece.unwrap()).collect();
+    let fen = format!("{}/pppppppp/8/8/8/8/PPPPPPPP/{} w KQkq - 0 1", white.to_ascii_lowercase(), white);
+    return Some(Self::build(&fen));
+  }
+
   fn calc_occupancy(&mut self) {
     self.occupancy = [0u64; 3];
     for b in 0..6 {
@@ -292,4 +485,141 @@ impl Board {
 
     return None;
   }
-}
\ No newline at end of file
+}
+
+// <----- TESTS ----->
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn try_from_fen_test() {
+
+    let fens = [
+      "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+      "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1",
+      "rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+      "rnbqkbnr/ppppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+      "rnbqkbnr/pppppppp/7/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+      "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkx - 0 1",
+      "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KKkq - 0 1",
+      "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e3 0 1",
+      "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e9 0 1",
+      "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNK w kq - 0 1",
+      "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQ1BNR w kq - 0 1",
+      "rnbqkbnr/ppppp1pp/8/7Q/8/8/PPPPPPPP/RNB1KBNR w KQkq - 0 1"
+    ];
+    let expected_results = [
+      Ok(()),
+      Err(FenError::InvalidRankCount(7)),
+      Err(FenError::InvalidPiecePlacement('9')),
+      Err(FenError::RankOverflow(7)),
+      Err(FenError::IncompleteRank(6)),
+      Err(FenError::InvalidCastling("KQkx".to_string())),
+      Err(FenError::InvalidCastling("KKkq".to_string())),
+      Err(FenError::InvalidEnPassant("e3".to_string())),
+      Err(FenError::InvalidEnPassant("e9".to_string())),
+      Err(FenError::ExtraKings("white".to_string())),
+      Err(FenError::MissingKing("white".to_string())),
+      Err(FenError::OpponentInCheck)
+    ];
+
+    for test_nr in 0..12 {
+      let actual = Board::try_from_fen(fens[test_nr]).map(|_| ());
+      assert_eq!(actual, expected_results[test_nr]);
+    }
+  }
+
+  #[test]
+  fn try_from_fen_en_passant_test() {
+
+    let fens = [
+      "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 1",
+      "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 1"
+    ];
+    let expected_results: [u64; 2] = [1 << 43, 0];
+
+    for test_nr in 0..2 {
+      let board = Board::try_from_fen(fens[test_nr]).unwrap();
+      assert_eq!(board.en_passant_square(), expected_results[test_nr]);
+    }
+  }
+
+  #[test]
+  fn fen_round_trip_test() {
+
+    let fens = [
+      "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+      "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+      "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3",
+      "8/8/4k3/8/8/3K4/8/8 b - - 87 112"
+    ];
+
+    for test_nr in 0..4 {
+      let board = Board::try_from_fen(fens[test_nr]).unwrap();
+      assert_eq!(board.fen(), fens[test_nr]);
+    }
+  }
+
+  #[test]
+  fn move_counters_parse_test() {
+
+    let fens = [
+      "8/8/4k3/8/8/3K4/8/8 b - -",
+      "8/8/4k3/8/8/3K4/8/8 b - - x 1",
+      "8/8/4k3/8/8/3K4/8/8 b - - 0 0"
+    ];
+    let expected_results = [
+      Ok((0, 1)),
+      Err(FenError::InvalidHalfmoveClock("x".to_string())),
+      Err(FenError::InvalidFullmoveNumber("0".to_string()))
+    ];
+
+    for test_nr in 0..3 {
+      let actual = Board::try_from_fen(fens[test_nr]).map(|board| (board.halfmove_clock(), board.fullmove_number()));
+      assert_eq!(actual, expected_results[test_nr]);
+    }
+  }
+
+  #[test]
+  fn chess960_castling_parse_test() {
+
+    let fens = [
+      "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+      "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KFhq - 2 9",
+      "4k3/8/8/8/8/8/8/RR2K3 w B - 0 1",
+      "4k3/8/8/8/8/8/8/RR2K3 w Q - 0 1",
+      "4k3/8/8/8/8/8/8/RR2K3 w C - 0 1",
+      "4k3/8/8/8/8/8/8/RR2K3 w K - 0 1",
+      "4k3/8/8/8/8/8/8/RR2K3 w QA - 0 1"
+    ];
+    let expected_results = [
+      Ok("KQkq".to_string()),
+      Ok("KQkq".to_string()),
+      Ok("B".to_string()),
+      Ok("Q".to_string()),
+      Err(FenError::InvalidCastling("C".to_string())),
+      Err(FenError::InvalidCastling("K".to_string())),
+      Err(FenError::InvalidCastling("QA".to_string()))
+    ];
+
+    for test_nr in 0..7 {
+      let actual = Board::try_from_fen(fens[test_nr]).map(|board| board.fen().split_whitespace().nth(2).unwrap().to_string());
+      assert_eq!(actual, expected_results[test_nr]);
+    }
+  }
+
+  #[test]
+  fn new_chess960_test() {
+    assert_eq!(Board::new_chess960(518).unwrap().fen(), Board::new().fen());
+    assert_eq!(Board::new_chess960(0).unwrap().fen(), "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1");
+    assert_eq!(Board::new_chess960(959).unwrap().fen(), "rkrnnqbb/pppppppp/8/8/8/8/PPPPPPPP/RKRNNQBB w KQkq - 0 1");
+    assert!(Board::new_chess960(960).is_none());
+
+    let mut placements: Vec<String> = (0..960).map(|index| Board::new_chess960(index).unwrap().fen()).collect();
+    placements.sort();
+    placements.dedup();
+    assert_eq!(placements.len(), 960);
+  }
+}
@@ -0,0 +1,91 @@
+use super::board::Board;
+use crate::movefilter::MoveFilter;
+use super::bitmove::BitMoveType;
+use super::zobrist::ZOBRIST_KEYS;
+
+impl Board {
+
+  // bit 0 is a1, a dark square, so the lowest byte of the light squares is 0xAA (b1, d1, f1, h1)
+  const LIGHT_SQUARES: u64 = 0x55AA_55AA_55AA_55AA;
+  const DARK_SQUARES: u64 = 0xAA55_AA55_AA55_AA55;
+
+  // K vs K, K+minor vs K and positions where every bishop stands on the same colour
+  pub fn is_insufficient_material(&self) -> bool {
+    let heavy_pieces = self.bitboards[0] | self.bitboards[3] | self.bitboards[4]
+                     | self.bitboards[6] | self.bitboards[9] | self.bitboards[10];
+    if heavy_pieces != 0 {
+      return false;
+    }
+
+    let knights = self.bitboards[1] | self.bitboards[7];
+    let bishops = self.bitboards[2] | self.bitboards[8];
+    if (knights | bishops).count_ones() <= 1 {
+      return true;
+    }
+    return knights == 0
+      && (bishops & Self::LIGHT_SQUARES == 0 || bishops & Self::DARK_SQUARES == 0);
+  }
+
+  // whether the side could deliver mate by any series of legal moves (the FIDE timeout rule), so the
+  // opponent's pieces count as blockers: a single minor piece can mate when the opponent has anything
+  // besides its king, but nobody can be mated when the only pieces left are bishops on one colour
+  pub fn has_mating_material(&self, side: u8) -> bool {
+    let offset = 6 * side as usize;
+    let opponent_offset = 6 - offset;
+    let heavy_pieces = self.bitboards[offset] | self.bitboards[3 + offset] | self.bitboards[4 + offset];
+    if heavy_pieces != 0 {
+      return true;
+    }
+
+    let knights = self.bitboards[1 + offset];
+    let bishops = self.bitboards[2 + offset];
+    if knights | bishops == 0 {
+      return false;
+    }
+
+    let opponent_bishops = self.bitboards[2 + opponent_offset];
+    let opponent_others = self.bitboards[opponent_offset] | self.bitboards[1 + opponent_offset]
+                        | self.bitboards[3 + opponent_offset] | self.bitboards[4 + opponent_offset];
+    let all_bishops = bishops | opponent_bishops;
+    if knights == 0 && opponent_others == 0
+      && (all_bishops & Self::LIGHT_SQUARES == 0 || all_bishops & Self::DARK_SQUARES == 0) {
+      return false;
+    }
+    if (knights | bishops).count_ones() >= 2 {
+      return true;
+    }
+    return opponent_others | opponent_bishops != 0;
+  }
+
+  // two positions are the same for repetition purposes if the placement, side to move,
+  // castling rights and the possible en passant captures are all identical
+  pub fn repetition_key(&mut self) -> u64 {
+    if self.en_passant_square == 0 {
+      return self.hash;
+    }
+
+    let can_capture = self.legal_moves(MoveFilter::Captures)
+      .any(|bitmove| bitmove.move_type() == BitMoveType::EnPassant);
+    if can_capture {
+      return self.hash;
+    }
+    return self.hash ^ ZOBRIST_KEYS.en_passant[self.en_passant_square.trailing_zeros() as usize % 8];
+  }
+}
+
+// <----- TESTS ----->
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn square_colour_test() {
+    // a1 and h8 are dark, h1 and a8 are light
+    for (square, dark) in [(0, true), (63, true), (7, false), (56, false)] {
+      assert_eq!(Board::DARK_SQUARES & (1u64 << square) != 0, dark);
+      assert_eq!(Board::LIGHT_SQUARES & (1u64 << square) != 0, !dark);
+    }
+    assert_eq!(Board::DARK_SQUARES | Board::LIGHT_SQUARES, u64::MAX);
+  }
+}
@@ -0,0 +1,457 @@
+use super::board::Board;
+use super::attackmaps::{KING_ATTACK_MAP, PAWN_ATTACK_MAP};
+
+/*
+ EXPLANATIONS:
+ > every term is calculated for white minus black as a (middlegame, endgame) pair
+ > the two halves are blended by the game phase, which goes from 24 (all pieces on the board) to 0 (bare kings and pawns)
+ > piece-square tables are written from white's point of view with a8 in the top left corner,
+   so a white piece on <square_index> reads entry <square_index> ^ 56, a black one reads <square_index>
+*/
+
+const PIECE_VALUES_MG: [i32; 6] = [82, 337, 365, 477, 1025, 0];
+const PIECE_VALUES_EG: [i32; 6] = [94, 281, 297, 512, 936, 0];
+const PHASE_WEIGHTS: [i32; 6] = [0, 1, 1, 2, 4, 0];
+const MAX_PHASE: i32 = 24;
+
+// per reachable square, for knights, bishops, rooks and queens
+const MOBILITY_MG: [i32; 4] = [4, 5, 2, 1];
+const MOBILITY_EG: [i32; 4] = [4, 5, 4, 2];
+
+const DOUBLED_PAWN: (i32, i32) = (-10, -20);
+const ISOLATED_PAWN: (i32, i32) = (-12, -15);
+// PASSED_PAWN_*[<ranks advanced from the pawn's starting rank>]
+const PASSED_PAWN_MG: [i32; 8] = [0, 5, 10, 15, 25, 45, 70, 0];
+const PASSED_PAWN_EG: [i32; 8] = [0, 10, 20, 35, 60, 100, 150, 0];
+const BISHOP_PAIR: (i32, i32) = (30, 50);
+
+const PAWN_SHIELD: i32 = 12;
+// KING_ZONE_ATTACK_WEIGHTS[<piece_type>], for attacks on the squares around the king
+const KING_ZONE_ATTACK_WEIGHTS: [i32; 5] = [0, 8, 8, 12, 20];
+
+const FILES: [u64; 8] = [
+  0x0101_0101_0101_0101, 0x0202_0202_0202_0202, 0x0404_0404_0404_0404, 0x0808_0808_0808_0808,
+  0x1010_1010_1010_1010, 0x2020_2020_2020_2020, 0x4040_4040_4040_4040, 0x8080_8080_8080_8080
+];
+
+const PAWN_TABLE_MG: [i32; 64] = [
+    0,   0,   0,   0,   0,   0,   0,   0,
+   50,  50,  50,  50,  50,  50,  50,  50,
+   10,  10,  20,  30,  30,  20,  10,  10,
+    5,   5,  10,  25,  25,  10,   5,   5,
+    0,   0,   0,  20,  20,   0,   0,   0,
+    5,  -5, -10,   0,   0, -10,  -5,   5,
+    5,  10,  10, -20, -20,  10,  10,   5,
+    0,   0,   0,   0,   0,   0,   0,   0
+];
+const PAWN_TABLE_EG: [i32; 64] = [
+    0,   0,   0,   0,   0,   0,   0,   0,
+   80,  80,  80,  80,  80,  80,  80,  80,
+   50,  50,  50,  50,  50,  50,  50,  50,
+   30,  30,  30,  30,  30,  30,  30,  30,
+   15,  15,  15,  15,  15,  15,  15,  15,
+    5,   5,   5,   5,   5,   5,   5,   5,
+    0,   0,   0,   0,   0,   0,   0,   0,
+    0,   0,   0,   0,   0,   0,   0,   0
+];
+const KNIGHT_TABLE_MG: [i32; 64] = [
+  -50, -40, -30, -30, -30, -30, -40, -50,
+  -40, -20,   0,   0,   0,   0, -20, -40,
+  -30,   0,  10,  15,  15,  10,   0, -30,
+  -30,   5,  15,  20,  20,  15,   5, -30,
+  -30,   0,  15,  20,  20,  15,   0, -30,
+  -30,   5,  10,  15,  15,  10,   5, -30,
+  -40, -20,   0,   5,   5,   0, -20, -40,
+  -50, -40, -30, -30, -30, -30, -40, -50
+];
+const KNIGHT_TABLE_EG: [i32; 64] = [
+  -50, -40, -30, -30, -30, -30, -40, -50,
+  -40, -20,   0,   0,   0,   0, -20, -40,
+  -30,   0,  10,  15,  15,  10,   0, -30,
+  -30,   0,  15,  20,  20,  15,   0, -30,
+  -30,   0,  15,  20,  20,  15,   0, -30,
+  -30,   0,  10,  15,  15,  10,   0, -30,
+  -40, -20,   0,   0,   0,   0, -20, -40,
+  -50, -40, -30, -30, -30, -30, -40, -50
+];
+const BISHOP_TABLE_MG: [i32; 64] = [
+  -20, -10, -10, -10, -10, -10, -10, -20,
+  -10,   0,   0,   0,   0,   0,   0, -10,
+  -10,   0,   5,  10,  10,   5,   0, -10,
+  -10,   5,   5,  10,  10,   5,   5, -10,
+  -10,   0,  10,  10,  10,  10,   0, -10,
+  -10,  10,  10,  10,  10,  10,  10, -10,
+  -10,   5,   0,   0,   0,   0,   5, -10,
+  -20, -10, -10, -10, -10, -10, -10, -20
+];
+const BISHOP_TABLE_EG: [i32; 64] = [
+  -20, -10, -10, -10, -10, -10, -10, -20,
+  -10,   0,   0,   0,   0,   0,   0, -10,
+  -10,   0,   5,  10,  10,   5,   0, -10,
+  -10,   0,  10,  15,  15,  10,   0, -10,
+  -10,   0,  10,  15,  15,  10,   0, -10,
+  -10,   0,   5,  10,  10,   5,   0, -10,
+  -10,   0,   0,   0,   0,   0,   0, -10,
+  -20, -10, -10, -10, -10, -10, -10, -20
+];
+const ROOK_TABLE_MG: [i32; 64] = [
+    0,   0,   0,   0,   0,   0,   0,   0,
+    5,  10,  10,  10,  10,  10,  10,   5,
+   -5,   0,   0,   0,   0,   0,   0,  -5,
+   -5,   0,   0,   0,   0,   0,   0,  -5,
+   -5,   0,   0,   0,   0,   0,   0,  -5,
+   -5,   0,   0,   0,   0,   0,   0,  -5,
+   -5,   0,   0,   0,   0,   0,   0,  -5,
+    0,   0,   0,   5,   5,   0,   0,   0
+];
+const ROOK_TABLE_EG: [i32; 64] = [
+   10,  10,  10,  10,  10,  10,  10,  10,
+   15,  15,  15,  15,  15,  15,  15,  15,
+    5,   5,   5,   5,   5,   5,   5,   5,
+    0,   0,   0,   0,   0,   0,   0,   0,
+    0,   0,   0,   0,   0,   0,   0,   0,
+    0,   0,   0,   0,   0,   0,   0,   0,
+    0,   0,   0,   0,   0,   0,   0,   0,
+    0,   0,   0,   0,   0,   0,   0,   0
+];
+const QUEEN_TABLE_MG: [i32; 64] = [
+  -20, -10, -10,  -5,  -5, -10, -10, -20,
+  -10,   0,   0,   0,   0,   0,   0, -10,
+  -10,   0,   5,   5,   5,   5,   0, -10,
+   -5,   0,   5,   5,   5,   5,   0,  -5,
+    0,   0,   5,   5,   5,   5,   0,  -5,
+  -10,   5,   5,   5,   5,   5,   0, -10,
+  -10,   0,   5,   0,   0,   0,   0, -10,
+  -20, -10, -10,  -5,  -5, -10, -10, -20
+];
+const QUEEN_TABLE_EG: [i32; 64] = [
+  -20, -10, -10,  -5,  -5, -10, -10, -20,
+  -10,   0,   5,   5,   5,   5,   0, -10,
+  -10,   5,  10,  10,  10,  10,   5, -10,
+   -5,   5,  10,  15,  15,  10,   5,  -5,
+   -5,   5,  10,  15,  15,  10,   5,  -5,
+  -10,   5,  10,  10,  10,  10,   5, -10,
+  -10,   0,   5,   5,   5,   5,   0, -10,
+  -20, -10, -10,  -5,  -5, -10, -10, -20
+];
+const KING_TABLE_MG: [i32; 64] = [
+  -30, -40, -40, -50, -50, -40, -40, -30,
+  -30, -40, -40, -50, -50, -40, -40, -30,
+  -30, -40, -40, -50, -50, -40, -40, -30,
+  -30, -40, -40, -50, -50, -40, -40, -30,
+  -20, -30, -30, -40, -40, -30, -30, -20,
+  -10, -20, -20, -20, -20, -20, -20, -10,
+   20,  20,   0,   0,   0,   0,  20,  20,
+   20,  30,  10,   0,   0,  10,  30,  20
+];
+const KING_TABLE_EG: [i32; 64] = [
+  -50, -40, -30, -20, -20, -30, -40, -50,
+  -30, -20, -10,   0,   0, -10, -20, -30,
+  -30, -10,  20,  30,  30,  20, -10, -30,
+  -30, -10,  30,  40,  40,  30, -10, -30,
+  -30, -10,  30,  40,  40,  30, -10, -30,
+  -30, -10,  20,  30,  30,  20, -10, -30,
+  -30, -30,   0,   0,   0,   0, -30, -30,
+  -50, -30, -30, -30, -30, -30, -30, -50
+];
+
+// PIECE_SQUARE_TABLES[<piece_type>] -> (middlegame, endgame)
+const PIECE_SQUARE_TABLES: [(&[i32; 64], &[i32; 64]); 6] = [
+  (&PAWN_TABLE_MG, &PAWN_TABLE_EG),
+  (&KNIGHT_TABLE_MG, &KNIGHT_TABLE_EG),
+  (&BISHOP_TABLE_MG, &BISHOP_TABLE_EG),
+  (&ROOK_TABLE_MG, &ROOK_TABLE_EG),
+  (&QUEEN_TABLE_MG, &QUEEN_TABLE_EG),
+  (&KING_TABLE_MG, &KING_TABLE_EG)
+];
+
+impl Board {
+
+  // centipawns from the side to move's perspective
+  pub fn evaluate(&self) -> i32 {
+    let mut mg = 0i32;
+    let mut eg = 0i32;
+    let mut phase = 0i32;
+
+    for side in 0..2 {
+      let sign = if side == 0 { 1 } else { -1 };
+      let (side_mg, side_eg) = self.evaluate_side(side);
+      mg += sign * side_mg;
+      eg += sign * side_eg;
+
+      for piece_type in 0..6 {
+        phase += PHASE_WEIGHTS[piece_type] * self.bitboards[piece_type + 6 * side].count_ones() as i32;
+      }
+    }
+
+    // promotions can push the phase above the starting value
+    let phase = phase.min(MAX_PHASE);
+    let score = (mg * phase + eg * (MAX_PHASE - phase)) / MAX_PHASE;
+
+    return if self.side_to_move == 0 { score } else { -score };
+  }
+
+  fn evaluate_side(&self, side: usize) -> (i32, i32) {
+    let mut mg = 0i32;
+    let mut eg = 0i32;
+
+    let (material_mg, material_eg) = self.evaluate_material(side);
+    mg += material_mg;
+    eg += material_eg;
+
+    let (mobility_mg, mobility_eg) = self.evaluate_mobility(side);
+    mg += mobility_mg;
+    eg += mobility_eg;
+
+    let (pawns_mg, pawns_eg) = self.evaluate_pawn_structure(side);
+    mg += pawns_mg;
+    eg += pawns_eg;
+
+    // king safety only matters while there is enough material left to attack with,
+    // which the tapering takes care of
+    mg += self.evaluate_king_safety(side);
+
+    return (mg, eg);
+  }
+
+  fn evaluate_material(&self, side: usize) -> (i32, i32) {
+    let mut mg = 0i32;
+    let mut eg = 0i32;
+
+    for piece_type in 0..6 {
+      let (table_mg, table_eg) = PIECE_SQUARE_TABLES[piece_type];
+      let mut pieces = self.bitboards[piece_type + 6 * side];
+
+      while pieces != 0 {
+        let sq = pieces.trailing_zeros() as usize;
+        let table_sq = if side == 0 { sq ^ 56 } else { sq };
+
+        mg += PIECE_VALUES_MG[piece_type] + table_mg[table_sq];
+        eg += PIECE_VALUES_EG[piece_type] + table_eg[table_sq];
+        pieces &= !(1 << sq);
+      }
+    }
+
+    if self.bitboards[2 + 6 * side].count_ones() >= 2 {
+      mg += BISHOP_PAIR.0;
+      eg += BISHOP_PAIR.1;
+    }
+
+    return (mg, eg);
+  }
+
+  // squares reachable by the pseudo moves of the minor and major pieces,
+  // not counting the ones defended by an opponent pawn
+  fn evaluate_mobility(&self, side: usize) -> (i32, i32) {
+    let mut mg = 0i32;
+    let mut eg = 0i32;
+
+    let mut opponent_pawn_attacks = 0u64;
+    let mut opponent_pawns = self.bitboards[6 - 6 * side];
+    while opponent_pawns != 0 {
+      let sq = opponent_pawns.trailing_zeros() as usize;
+      opponent_pawn_attacks |= PAWN_ATTACK_MAP[sq][1 - side];
+      opponent_pawns &= !(1 << sq);
+    }
+    let available_squares = !self.occupancy[side] & !opponent_pawn_attacks;
+
+    for piece_type in 1..5 {
+      let mut pieces = self.bitboards[piece_type + 6 * side];
+
+      while pieces != 0 {
+        let sq = pieces.trailing_zeros();
+        let moves = match piece_type {
+          1 => self.get_pseudo_knight_moves(sq),
+          2 => self.get_pseudo_bishop_moves(sq),
+          3 => self.get_pseudo_rook_moves(sq),
+          _ => self.get_pseudo_queen_moves(sq)
+        };
+        let mobility = (moves & available_squares).count_ones() as i32;
+
+        mg += MOBILITY_MG[piece_type - 1] * mobility;
+        eg += MOBILITY_EG[piece_type - 1] * mobility;
+        pieces &= !(1 << sq);
+      }
+    }
+
+    return (mg, eg);
+  }
+
+  fn evaluate_pawn_structure(&self, side: usize) -> (i32, i32) {
+    let mut mg = 0i32;
+    let mut eg = 0i32;
+
+    let pawns = self.bitboards[6 * side];
+    let opponent_pawns = self.bitboards[6 - 6 * side];
+
+    for file in 0..8 {
+      let pawns_on_file = (pawns & FILES[file]).count_ones() as i32;
+      if pawns_on_file == 0 {
+        continue;
+      }
+
+      if pawns_on_file > 1 {
+        mg += DOUBLED_PAWN.0 * (pawns_on_file - 1);
+        eg += DOUBLED_PAWN.1 * (pawns_on_file - 1);
+      }
+
+      let neighbour_files = if file > 0 { FILES[file - 1] } else { 0 } | if file < 7 { FILES[file + 1] } else { 0 };
+      if pawns & neighbour_files == 0 {
+        mg += ISOLATED_PAWN.0 * pawns_on_file;
+        eg += ISOLATED_PAWN.1 * pawns_on_file;
+      }
+    }
+
+    let mut remaining_pawns = pawns;
+    while remaining_pawns != 0 {
+      let sq = remaining_pawns.trailing_zeros() as usize;
+      remaining_pawns &= !(1 << sq);
+
+      // no opponent pawn in front of it on its own or a neighbouring file
+      let file = sq % 8;
+      let files = FILES[file] | if file > 0 { FILES[file - 1] } else { 0 } | if file < 7 { FILES[file + 1] } else { 0 };
+      let rank = sq / 8;
+      let squares_in_front = if side == 0 { u64::MAX.checked_shl(8 * (rank as u32 + 1)) } else { u64::MAX.checked_shr(8 * (8 - rank as u32)) };
+      let squares_in_front = squares_in_front.unwrap_or(0);
+      if opponent_pawns & files & squares_in_front != 0 {
+        continue;
+      }
+
+      let advanced_ranks = if side == 0 { rank.saturating_sub(1) } else { 6usize.saturating_sub(rank) };
+      mg += PASSED_PAWN_MG[advanced_ranks];
+      eg += PASSED_PAWN_EG[advanced_ranks];
+    }
+
+    return (mg, eg);
+  }
+
+  // pawns in front of the king and opponent pieces eyeing the squares around it
+  fn evaluate_king_safety(&self, side: usize) -> i32 {
+    let mut score = 0i32;
+
+    let king_sq = self.bitboards[5 + 6 * side].trailing_zeros() as usize;
+    let king_zone = KING_ATTACK_MAP[king_sq] | 1 << king_sq;
+    let forward_zone = if side == 0 { king_zone << 8 } else { king_zone >> 8 };
+    score += PAWN_SHIELD * (forward_zone & self.bitboards[6 * side]).count_ones() as i32;
+
+    let opponent = 1 - side;
+    let mut attack_weight = 0i32;
+    let mut attacker_count = 0i32;
+    for piece_type in 1..5 {
+      let mut pieces = self.bitboards[piece_type + 6 * opponent];
+
+      while pieces != 0 {
+        let sq = pieces.trailing_zeros();
+        let moves = match piece_type {
+          1 => self.get_pseudo_knight_moves(sq),
+          2 => self.get_pseudo_bishop_moves(sq),
+          3 => self.get_pseudo_rook_moves(sq),
+          _ => self.get_pseudo_queen_moves(sq)
+        };
+        let attacked_squares = (moves & king_zone).count_ones() as i32;
+        if attacked_squares != 0 {
+          attack_weight += KING_ZONE_ATTACK_WEIGHTS[piece_type] * attacked_squares;
+          attacker_count += 1;
+        }
+        pieces &= !(1 << sq);
+      }
+    }
+
+    // a lone attacker is rarely dangerous, a coordinated attack is
+    if attacker_count >= 2 {
+      score -= attack_weight * attacker_count / 2;
+    }
+
+    return score;
+  }
+}
+
+
+// <----- TESTS ----->
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // flips the board vertically and swaps the colours, the evaluation must not change
+  fn mirror_fen(fen: &str) -> String {
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+    let placement: Vec<String> = fields[0].split('/').rev()
+      .map(|rank| rank.chars().map(|c| if c.is_ascii_uppercase() { c.to_ascii_lowercase() } else { c.to_ascii_uppercase() }).collect())
+      .collect();
+    let side = if fields[1] == "w" { "b" } else { "w" };
+    let mut castling: Vec<char> = fields[2].chars()
+      .map(|c| if c.is_ascii_uppercase() { c.to_ascii_lowercase() } else { c.to_ascii_uppercase() })
+      .collect();
+    castling.sort_by_key(|c| (c.is_ascii_lowercase(), *c != 'K' && *c != 'k'));
+    let en_passant: String = fields[3].chars()
+      .map(|c| if c.is_ascii_digit() { (b'9' - c as u8 + b'0') as char } else { c })
+      .collect();
+
+    return format!("{} {} {} {} {} {}", placement.join("/"), side, castling.iter().collect::<String>(), en_passant, fields[4], fields[5]);
+  }
+
+  #[test]
+  fn start_position_test() {
+    assert_eq!(Board::new().evaluate(), 0);
+  }
+
+  #[test]
+  fn symmetry_test() {
+    let fens = [
+      "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+      "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+      "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+      "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8"
+    ];
+
+    for fen in fens {
+      let board = Board::build(fen);
+      let mirrored = Board::build(&mirror_fen(fen));
+      assert_eq!(board.evaluate(), mirrored.evaluate(), "{}", fen);
+    }
+  }
+
+  #[test]
+  fn side_to_move_perspective_test() {
+    let white_to_move = Board::build("4k3/8/8/8/8/8/3Q4/4K3 w - - 0 1");
+    let black_to_move = Board::build("4k3/8/8/8/8/8/3Q4/4K3 b - - 0 1");
+
+    assert!(white_to_move.evaluate() > 800);
+    assert_eq!(white_to_move.evaluate(), -black_to_move.evaluate());
+  }
+
+  #[test]
+  fn pawn_structure_test() {
+    // the same pawns, except white has a doubled one
+    let board = Board::build("4k3/p4pp1/8/8/8/5P2/P4PP1/4K3 w - - 0 1");
+    let (healthy_mg, healthy_eg) = board.evaluate_pawn_structure(1);
+    let (weak_mg, weak_eg) = board.evaluate_pawn_structure(0);
+    assert!(weak_mg < healthy_mg && weak_eg < healthy_eg);
+
+    // a passed pawn counts more the further it has advanced
+    let advanced = Board::build("4k3/8/1P6/8/8/8/8/4K3 w - - 0 1");
+    let behind = Board::build("4k3/8/8/8/8/1P6/8/4K3 w - - 0 1");
+    assert!(advanced.evaluate_pawn_structure(0).1 > behind.evaluate_pawn_structure(0).1);
+
+    // a pawn facing an opponent pawn on the neighbouring file is not passed
+    let blocked = Board::build("4k3/p7/8/8/8/1P6/8/4K3 w - - 0 1");
+    assert!(blocked.evaluate_pawn_structure(0).1 < behind.evaluate_pawn_structure(0).1);
+  }
+
+  #[test]
+  fn king_safety_test() {
+    let sheltered = Board::build("r2q1rk1/ppp2ppp/8/8/8/8/PPP2PPP/R2Q1RK1 w - - 0 1");
+    let exposed = Board::build("r2q1rk1/ppp2ppp/8/8/8/8/PPP5/R2Q1RK1 w - - 0 1");
+
+    assert!(sheltered.evaluate_king_safety(0) > exposed.evaluate_king_safety(0));
+  }
+
+  #[test]
+  fn mobility_test() {
+    let active = Board::build("4k3/8/8/3B4/8/8/8/4K3 w - - 0 1");
+    let passive = Board::build("4k3/8/8/8/8/8/1P6/B3K3 w - - 0 1");
+
+    assert!(active.evaluate_mobility(0).0 > passive.evaluate_mobility(0).0);
+  }
+}
@@ -1,7 +1,6 @@
 use super::board::Board;
-use super::attackmaps::RAY_TABLE;
+use super::attackmaps::{RAY_TABLE, SQUARES_BETWEEN, ROOK_MAGICS, BISHOP_MAGICS};
 use super::checkinfo::CheckInfo;
-use super::attacks::get_raycast_from_square_in_direction;
 
 impl Board {
 
@@ -14,22 +13,16 @@ impl Board {
 
     // queen-rook checks (+)
     let attacker_mask = self.bitboards[10 - offset] | self.bitboards[9 - offset];
-
-    for dir in [0, 2, 4, 6] {
-      let threat_mask: u64 = get_raycast_from_square_in_direction(occupancy, king_sq, dir);
-      if threat_mask & attacker_mask != 0 {
-        check_info.add_checker(threat_mask);
-      }
-    }
+    let mut checkers = ROOK_MAGICS.get_attacks(king_sq, occupancy) & attacker_mask;
 
     // queen-bishop checks (x)
     let attacker_mask = self.bitboards[10 - offset] | self.bitboards[8 - offset];
+    checkers |= BISHOP_MAGICS.get_attacks(king_sq, occupancy) & attacker_mask;
 
-    for dir in [1, 3, 5, 7] {
-      let threat_mask = get_raycast_from_square_in_direction(occupancy, king_sq, dir);
-      if threat_mask & attacker_mask != 0 {
-        check_info.add_checker(threat_mask);
-      }
+    while checkers != 0 {
+      let checker_sq = checkers.trailing_zeros() as usize;
+      check_info.add_checker(SQUARES_BETWEEN[king_sq][checker_sq] | 1 << checker_sq);
+      checkers &= !(1 << checker_sq);
     }
 
     // knight checks (L)
@@ -113,7 +106,7 @@ mod tests {
 
     let fens = [
       "rnb1k1nr/pppppppp/4q3/8/1b6/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",      // no check
-      "rnb1k1nr/pppppppp/4q3/8/1b1P4/8/PPP1PPPP/RNBQKBNR w KQkq d3 0 1",   // single check
+      "rnb1k1nr/pppppppp/4q3/8/1b1P4/8/PPP1PPPP/RNBQKBNR w KQkq - 0 1",    // single check
       "rnb1k1nr/ppp1p2p/3pq1p1/8/1b1P1P2/8/PPP2PPP/RNBQKBNR w KQkq - 0 1"  // double check
       ];
     let expected_results = [
@@ -0,0 +1,118 @@
+use super::board::Board;
+use super::bitmove::BitMove;
+use super::movebuffer::MoveBuffer;
+use crate::movefilter::MoveFilter;
+
+// the legal moves of a position, generated once into a buffer that lives on the stack;
+// it does not borrow the board, so the moves can be played while iterating
+pub struct LegalMoves {
+  buffer: MoveBuffer,
+  next_idx: usize
+}
+
+impl Board {
+
+  pub fn legal_moves(&mut self, filter: MoveFilter) -> LegalMoves {
+    let mut buffer = MoveBuffer::new();
+    self.generate_moves(&mut buffer, filter);
+
+    return LegalMoves {
+      buffer,
+      next_idx: 0
+    };
+  }
+}
+
+impl Iterator for LegalMoves {
+  type Item = BitMove;
+
+  #[inline]
+  fn next(&mut self) -> Option<BitMove> {
+    if self.next_idx >= self.buffer.count() {
+      return None;
+    }
+    self.next_idx += 1;
+    return Some(*self.buffer.get(self.next_idx - 1));
+  }
+
+  #[inline]
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let remaining = self.buffer.count() - self.next_idx;
+    return (remaining, Some(remaining));
+  }
+}
+
+impl ExactSizeIterator for LegalMoves {}
+
+// <----- TESTS ----->
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const FENS: [&str; 6] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "1r4kr/pppppppp/8/8/8/8/PPPPPPPP/1R4KR w KQkq - 0 1"
+  ];
+
+  fn sorted(moves: impl Iterator<Item = BitMove>) -> Vec<String> {
+    let mut notations: Vec<String> = moves.map(|bitmove| format!("{:?}", bitmove)).collect();
+    notations.sort();
+    return notations;
+  }
+
+  #[test]
+  fn legal_moves_match_generate_moves_test() {
+    for fen in FENS {
+      let mut board = Board::build(fen);
+      let mut buffer = MoveBuffer::new();
+      let in_check = board.generate_moves(&mut buffer, MoveFilter::All);
+      assert_eq!(in_check, board.check_test().check_count > 0);
+
+      let legal_moves = board.legal_moves(MoveFilter::All);
+      assert_eq!(legal_moves.len(), buffer.count());
+      assert_eq!(sorted(legal_moves), sorted(buffer.contents().iter().copied()), "{}", fen);
+    }
+  }
+
+  #[test]
+  fn captures_first_test() {
+    for fen in FENS {
+      let mut board = Board::build(fen);
+      let all: Vec<BitMove> = board.legal_moves(MoveFilter::All).collect();
+      let captures: Vec<BitMove> = board.legal_moves(MoveFilter::Captures).collect();
+      let quiets: Vec<BitMove> = board.legal_moves(MoveFilter::Quiets).collect();
+
+      assert_eq!(all, [captures.clone(), quiets].concat());
+      assert!(captures.iter().all(|bitmove| board.piece_board(bitmove.to_square()) != Board::EMPTY_SQUARE
+        || bitmove.to_square() as u32 == board.en_passant_square().trailing_zeros()));
+    }
+  }
+
+  #[test]
+  fn checks_test() {
+    // queen and rook checks along files, ranks and diagonals
+    let mut board = Board::build("6k1/p7/8/8/8/8/1R6/KQ6 w - - 0 1");
+    let checks: Vec<String> = board.legal_moves(MoveFilter::Checks).map(|bitmove| bitmove.uci_notation()).collect();
+    let all = board.legal_moves(MoveFilter::All);
+
+    for bitmove in all {
+      board.make_move(&bitmove);
+      let gives_check = board.check_test().check_count > 0;
+      board.unmake_move();
+      assert_eq!(checks.contains(&bitmove.uci_notation()), gives_check);
+    }
+    assert!(checks.contains(&"b1g6".to_string()));
+    assert!(checks.contains(&"b2b8".to_string()));
+    assert!(checks.contains(&"b2g2".to_string()));
+
+    // in double check only the king moves, the rook may not take the checking rook
+    let mut board = Board::build("4k3/8/8/8/8/5n2/R7/r3K3 w - - 0 1");
+    assert_eq!(board.legal_moves(MoveFilter::Captures).len(), 0);
+    assert!(board.legal_moves(MoveFilter::All).all(|bitmove| bitmove.from_square() == 4));
+  }
+}
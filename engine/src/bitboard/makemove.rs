@@ -1,10 +1,13 @@
 mod quiets;
 mod captures;
 mod castles;
+mod en_passants;
 
 use super::bitmove::BitMoveType;
 use super::bitmove::BitMove;
 use super::board::Board;
+use super::undoinfo::UndoInfo;
+use super::zobrist::ZOBRIST_KEYS;
 
 impl Board {
 
@@ -12,6 +15,29 @@ impl Board {
   pub fn make_move(&mut self, played_move: &BitMove) {
     let move_type = played_move.move_type();
 
+    let captured_piece = match move_type {
+      BitMoveType::Capture => self.piece_board(played_move.to_square()),
+      BitMoveType::EnPassant => 6 - 6 * self.side_to_move,
+      _ => Self::EMPTY_SQUARE
+    };
+    self.undo_stack.push(UndoInfo {
+      played_move: *played_move,
+      captured_piece,
+      castling_rights: self.castling_rights,
+      en_passant_square: self.en_passant_square,
+      halfmove_clock: self.halfmove_clock,
+      fullmove_number: self.fullmove_number,
+      hash: self.hash
+    });
+
+    self.hash ^= ZOBRIST_KEYS.castling[self.castling_rights as usize];
+    if self.en_passant_square != 0 {
+      self.hash ^= ZOBRIST_KEYS.en_passant[self.en_passant_square.trailing_zeros() as usize % 8];
+    }
+
+    // only a double pawn push played right now can set a new en passant square
+    self.en_passant_square = 0u64;
+
     match move_type {
       BitMoveType::Quiet => {
         self.make_quiet(played_move);
@@ -23,16 +49,138 @@ impl Board {
         self.make_castle(played_move);
       }
       BitMoveType::EnPassant => {
-        
+        self.make_en_passant(played_move);
       }
     }
 
     self.occupancy[2] = self.occupancy[0] | self.occupancy[1];
+    if self.castling_rights != 0 {
+      self.update_castling_rights(played_move);
+    }
 
+    if self.side_to_move == 1 {
+      self.fullmove_number = self.fullmove_number.saturating_add(1);
+    }
+    self.side_to_move = 1 - self.side_to_move;
+
+    self.hash ^= ZOBRIST_KEYS.castling[self.castling_rights as usize];
     if self.en_passant_square != 0 {
-      self.en_passant_square = 0u64;
+      self.hash ^= ZOBRIST_KEYS.en_passant[self.en_passant_square.trailing_zeros() as usize % 8];
     }
+    self.hash ^= ZOBRIST_KEYS.side;
+
+    debug_assert_eq!(self.hash, self.calc_hash(), "incremental zobrist hash diverged");
+  }
+  pub fn unmake_move(&mut self) -> Option<BitMove> {
+    let undo_info = self.undo_stack.pop()?;
+    let played_move = undo_info.played_move;
 
     self.side_to_move = 1 - self.side_to_move;
+
+    match played_move.move_type() {
+      BitMoveType::Quiet => {
+        self.unmake_quiet(&played_move);
+      }
+      BitMoveType::Capture => {
+        self.unmake_capture(&played_move, undo_info.captured_piece);
+      }
+      BitMoveType::Castle => {
+        self.unmake_castle(&played_move);
+      }
+      BitMoveType::EnPassant => {
+        self.unmake_en_passant(&played_move);
+      }
+    }
+
+    self.occupancy[2] = self.occupancy[0] | self.occupancy[1];
+
+    self.castling_rights = undo_info.castling_rights;
+    self.en_passant_square = undo_info.en_passant_square;
+    self.halfmove_clock = undo_info.halfmove_clock;
+    self.fullmove_number = undo_info.fullmove_number;
+    self.hash = undo_info.hash;
+    self.calc_pinned_squares();
+
+    return Some(played_move);
+  }
+
+  // a king move gives up both rights of its side, a rook leaving or being taken on its starting square gives up its own
+  fn update_castling_rights(&mut self, played_move: &BitMove) {
+    let from_sq = played_move.from_square();
+    let to_sq = played_move.to_square();
+    let moved_piece = self.piece_board(to_sq);
+
+    if moved_piece % 6 == 5 {
+      self.castling_rights &= !(3 << (2 - 2 * (moved_piece / 6)));
+    }
+    for right_idx in 0..4 {
+      let rook_sq = self.castling_rooks[right_idx];
+      if rook_sq == from_sq || rook_sq == to_sq {
+        self.castling_rights &= !(1 << right_idx);
+      }
+    }
+  }
+}
+
+
+// <----- TESTS ----->
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::movefilter::MoveFilter;
+
+  fn snapshot(board: &Board) -> ([u64; 12], [u8; 64], [u64; 3], u8, [u8; 64], u64, u64, u8, u16, u16, u64) {
+    return (
+      board.bitboards,
+      board.piece_board,
+      board.occupancy,
+      board.castling_rights,
+      board.pinned_squares,
+      board.pin_mask,
+      board.en_passant_square,
+      board.side_to_move,
+      board.halfmove_clock,
+      board.fullmove_number,
+      board.hash
+    );
+  }
+
+  #[test]
+  fn unmake_move_test() {
+
+    let fens = [
+      "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+      "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+      "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+      "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+      "8/8/8/2k5/3Pp3/8/8/4K3 b - d3 5 40",
+      "1r4kr/pppppppp/8/8/8/8/PPPPPPPP/1R4KR w KQkq - 0 1"
+    ];
+
+    for fen in fens {
+      let mut board = Board::build(fen);
+      // generating the moves records the pins, so the snapshots are taken after it
+      let played_moves = board.legal_moves(MoveFilter::All);
+      let before = snapshot(&board);
+
+      for played_move in played_moves {
+        board.make_move(&played_move);
+
+        // one reply deep, so moves are also undone from positions reached by make_move
+        let replies = board.legal_moves(MoveFilter::All);
+        let after = snapshot(&board);
+        for reply in replies {
+          board.make_move(&reply);
+          assert!(board.unmake_move() == Some(reply));
+          assert!(snapshot(&board) == after);
+        }
+
+        assert!(board.unmake_move() == Some(played_move));
+        assert!(snapshot(&board) == before, "unmake failed for {} in {}", played_move.uci_notation(), fen);
+        assert_eq!(board.fen(), fen);
+      }
+      assert!(board.unmake_move().is_none());
+    }
   }
-}
\ No newline at end of file
+}
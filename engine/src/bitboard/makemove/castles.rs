@@ -1,4 +1,5 @@
 use super::*;
+use super::super::utils::notation_from_square_number;
 
 impl Board {
   pub fn make_castle(&mut self, played_move: &BitMove) {
@@ -10,26 +11,76 @@ impl Board {
     let castling_offset = 2 - 2 * self.side_to_move as usize;
 
     let secondary_piece: usize = main_piece - 2;
-    let is_kingside = main_to%8 > 4;
-    let secondary_from: usize = if is_kingside { main_to + 1 } else { main_to - 2 };
-    let secondary_to: usize = if is_kingside { main_to - 1 } else { main_to + 1 };
+    let (secondary_from, secondary_to) = self.castle_rook_squares(played_move);
 
-    self.bitboards[main_piece] |= 1 << main_to;
-    self.occupancy[friendly_occupancy] |= 1 << main_to;
-    self.piece_board[main_to] = main_piece as u8;
+    self.halfmove_clock = self.halfmove_clock.saturating_add(1);
 
+    // in chess960 the king and the rook can land on each other's starting square (or stay in place),
+    // so both are lifted before either is put down
     self.bitboards[main_piece] &= !(1 << main_from);
     self.occupancy[friendly_occupancy] &= !(1 << main_from);
     self.piece_board[main_from] = Self::EMPTY_SQUARE;
-
-    self.bitboards[secondary_piece] |= 1 << secondary_to;
-    self.occupancy[friendly_occupancy] |= 1 << secondary_to;
-    self.piece_board[secondary_to] = secondary_piece as u8;
+    self.hash ^= ZOBRIST_KEYS.pieces[main_piece][main_from];
 
     self.bitboards[secondary_piece] &= !(1 << secondary_from);
     self.occupancy[friendly_occupancy] &= !(1 << secondary_from);
     self.piece_board[secondary_from] = Self::EMPTY_SQUARE;
+    self.hash ^= ZOBRIST_KEYS.pieces[secondary_piece][secondary_from];
+
+    self.bitboards[main_piece] |= 1 << main_to;
+    self.occupancy[friendly_occupancy] |= 1 << main_to;
+    self.piece_board[main_to] = main_piece as u8;
+    self.hash ^= ZOBRIST_KEYS.pieces[main_piece][main_to];
+
+    self.bitboards[secondary_piece] |= 1 << secondary_to;
+    self.occupancy[friendly_occupancy] |= 1 << secondary_to;
+    self.piece_board[secondary_to] = secondary_piece as u8;
+    self.hash ^= ZOBRIST_KEYS.pieces[secondary_piece][secondary_to];
 
     self.castling_rights &= !(3 << castling_offset);
   }
-}
\ No newline at end of file
+  pub fn unmake_castle(&mut self, played_move: &BitMove) {
+    let main_from: usize = played_move.from_square() as usize;
+    let main_to: usize = played_move.to_square() as usize;
+    let main_piece: usize = self.piece_board(main_to as u8) as usize;
+    let friendly_occupancy = main_piece/6;
+
+    let secondary_piece: usize = main_piece - 2;
+    let (secondary_from, secondary_to) = self.castle_rook_squares(played_move);
+
+    self.bitboards[main_piece] &= !(1 << main_to);
+    self.occupancy[friendly_occupancy] &= !(1 << main_to);
+    self.piece_board[main_to] = Self::EMPTY_SQUARE;
+
+    self.bitboards[secondary_piece] &= !(1 << secondary_to);
+    self.occupancy[friendly_occupancy] &= !(1 << secondary_to);
+    self.piece_board[secondary_to] = Self::EMPTY_SQUARE;
+
+    self.bitboards[main_piece] |= 1 << main_from;
+    self.occupancy[friendly_occupancy] |= 1 << main_from;
+    self.piece_board[main_from] = main_piece as u8;
+
+    self.bitboards[secondary_piece] |= 1 << secondary_from;
+    self.occupancy[friendly_occupancy] |= 1 << secondary_from;
+    self.piece_board[secondary_from] = secondary_piece as u8;
+  }
+
+  // (from, to) of the rook; the king always ends up on the g or c file, the rook next to it on the f or d file
+  pub fn castle_rook_squares(&self, played_move: &BitMove) -> (usize, usize) {
+    let king_to = played_move.to_square() as usize;
+    let back_rank = king_to - king_to % 8;
+    let is_kingside = king_to % 8 == 6;
+    let right_idx = if is_kingside { 1 } else { 0 } + if back_rank == 0 { 2 } else { 0 };
+
+    let rook_from = self.castling_rooks[right_idx] as usize;
+    let rook_to = if is_kingside { back_rank + 5 } else { back_rank + 3 };
+    return (rook_from, rook_to);
+  }
+  // king takes own rook (e.g. e1h1), the form chess960 uses instead of the king's destination
+  pub fn castle_uci_notation(&self, played_move: &BitMove) -> String {
+    let (rook_from, _) = self.castle_rook_squares(played_move);
+    let mut notation = notation_from_square_number(played_move.from_square());
+    notation.push_str(&notation_from_square_number(rook_from as u8));
+    return notation;
+  }
+}
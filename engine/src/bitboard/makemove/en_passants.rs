@@ -0,0 +1,53 @@
+use super::*;
+
+impl Board {
+  pub fn make_en_passant(&mut self, played_move: &BitMove) {
+    let main_from: usize = played_move.from_square() as usize;
+    let main_to: usize = played_move.to_square() as usize;
+    let main_piece: usize = self.piece_board(main_from as u8) as usize;
+    let friendly_occupancy = main_piece/6;
+
+    let secondary_from: usize = if self.side_to_move == 0 { main_to - 8 } else { main_to + 8 };
+    let secondary_piece: usize = self.piece_board(secondary_from as u8) as usize;
+    let opponent_occupancy = 1 - self.side_to_move as usize;
+
+    self.halfmove_clock = 0;
+
+    self.bitboards[main_piece] &= !(1 << main_from);
+    self.occupancy[friendly_occupancy] &= !(1 << main_from);
+    self.piece_board[main_from] = Self::EMPTY_SQUARE;
+    self.hash ^= ZOBRIST_KEYS.pieces[main_piece][main_from];
+
+    self.bitboards[main_piece] |= 1 << main_to;
+    self.occupancy[friendly_occupancy] |= 1 << main_to;
+    self.piece_board[main_to] = main_piece as u8;
+    self.hash ^= ZOBRIST_KEYS.pieces[main_piece][main_to];
+
+    self.bitboards[secondary_piece] &= !(1 << secondary_from);
+    self.occupancy[opponent_occupancy] &= !(1 << secondary_from);
+    self.piece_board[secondary_from] = Self::EMPTY_SQUARE;
+    self.hash ^= ZOBRIST_KEYS.pieces[secondary_piece][secondary_from];
+  }
+  pub fn unmake_en_passant(&mut self, played_move: &BitMove) {
+    let main_from: usize = played_move.from_square() as usize;
+    let main_to: usize = played_move.to_square() as usize;
+    let main_piece: usize = self.piece_board(main_to as u8) as usize;
+    let friendly_occupancy = main_piece/6;
+
+    let secondary_from: usize = if self.side_to_move == 0 { main_to - 8 } else { main_to + 8 };
+    let secondary_piece: usize = 6 - 6 * self.side_to_move as usize;
+    let opponent_occupancy = 1 - self.side_to_move as usize;
+
+    self.bitboards[main_piece] &= !(1 << main_to);
+    self.occupancy[friendly_occupancy] &= !(1 << main_to);
+    self.piece_board[main_to] = Self::EMPTY_SQUARE;
+
+    self.bitboards[main_piece] |= 1 << main_from;
+    self.occupancy[friendly_occupancy] |= 1 << main_from;
+    self.piece_board[main_from] = main_piece as u8;
+
+    self.bitboards[secondary_piece] |= 1 << secondary_from;
+    self.occupancy[opponent_occupancy] |= 1 << secondary_from;
+    self.piece_board[secondary_from] = secondary_piece as u8;
+  }
+}
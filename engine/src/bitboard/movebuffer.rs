@@ -30,10 +30,18 @@ impl MoveBuffer {
     self.count = 0;
   }
   #[inline(always)]
+  pub fn truncate(&mut self, count: usize) {
+    self.count = self.count.min(count);
+  }
+  #[inline(always)]
   pub fn count(&self) -> usize{
     return self.count;
   }
   #[inline(always)]
+  pub fn swap(&mut self, a: usize, b: usize) {
+    self.buffer.swap(a, b);
+  }
+  #[inline(always)]
   pub fn get(&self, idx: usize) -> &BitMove {
     return &self.buffer[idx];
   }
@@ -8,60 +8,65 @@ mod kings;
 use super::board::Board;
 use super::movebuffer::MoveBuffer;
 use super::bitmove::BitMove;
-use super::checkinfo::CheckInfo;
 use super::utils::*;
+use super::attackmaps::{ROOK_MAGICS, BISHOP_MAGICS, SQUARES_BETWEEN};
+use crate::movefilter::MoveFilter;
 
 impl Board {
 
   const NO_FILTER: u64 = 0xFFFF_FFFF_FFFF_FFFF;
 
-  pub fn collect_moves(&mut self, buffer: &mut MoveBuffer, temp_buffer: &mut MoveBuffer) -> bool {
+  // fills a single buffer with the legal moves the filter asks for, captures always come before quiets;
+  // returns whether the side to move is in check
+  pub fn generate_moves(&mut self, buffer: &mut MoveBuffer, filter: MoveFilter) -> bool {
     buffer.clear();
     self.calc_pinned_squares();
     let check_info = self.check_test();
+    let move_mask = match check_info.check_count {
+      0 => Self::NO_FILTER,
+      1 => check_info.move_mask,
+      _ => 0u64 // double check, only the king can move
+    };
+    let safe_squares = self.get_safe_king_squares();
 
-    match check_info.check_count {
-      0 => self.collect_all_moves(buffer, temp_buffer),
-      1 => self.collect_moves_single_check(buffer, temp_buffer, &check_info),
-      2 => self.collect_king_evasion(buffer, temp_buffer),
-      _ => panic!("More than 2 checking pieces found as the same time!")
+    if filter != MoveFilter::Quiets {
+      self.add_pawn_captures(buffer, move_mask);
+      self.add_pawn_en_passant(buffer, move_mask);
+      self.add_knight_captures(buffer, move_mask);
+      self.add_bishop_captures(buffer, move_mask);
+      self.add_rook_captures(buffer, move_mask);
+      self.add_queen_captures(buffer, move_mask);
+      self.add_king_captures(buffer, safe_squares);
+    }
+    if filter != MoveFilter::Captures {
+      self.add_pawn_quiets(buffer, move_mask);
+      self.add_knight_quiets(buffer, move_mask);
+      self.add_bishop_quiets(buffer, move_mask);
+      self.add_rook_quiets(buffer, move_mask);
+      self.add_queen_quiets(buffer, move_mask);
+      self.add_king_quiets(buffer, safe_squares);
+      if check_info.check_count == 0 {
+        self.add_king_castles(buffer);
+      }
+    }
+    if filter == MoveFilter::Checks {
+      self.retain_checks(buffer);
     }
     return check_info.check_count > 0;
   }
+  fn retain_checks(&mut self, buffer: &mut MoveBuffer) {
+    let mut kept = 0;
+    for idx in 0..buffer.count() {
+      let next_move = *buffer.get(idx);
+      self.make_move(&next_move);
+      let gives_check = self.check_test().check_count > 0;
+      self.unmake_move();
 
-  pub(in super) fn collect_all_moves(&self, buffer: &mut MoveBuffer, temp_buffer: &mut MoveBuffer) {
-    let safe_squares = self.get_safe_king_squares();
-
-    self.add_pawn_moves(buffer, temp_buffer, Self::NO_FILTER);
-    self.add_knight_moves(buffer, temp_buffer, Self::NO_FILTER);
-    self.add_bishop_moves(buffer, temp_buffer, Self::NO_FILTER);
-    self.add_rook_moves(buffer, temp_buffer, Self::NO_FILTER);
-    self.add_queen_moves(buffer, temp_buffer, Self::NO_FILTER);
-    self.add_king_moves(buffer, temp_buffer, safe_squares);
-    self.add_king_castles(buffer, safe_squares);
-
-    buffer.append(temp_buffer);
-    temp_buffer.clear();
-  }
-  pub(in super) fn collect_moves_single_check(&self, buffer: &mut MoveBuffer, temp_buffer: &mut MoveBuffer, check_info: &CheckInfo) {
-    let safe_squares = self.get_safe_king_squares();
-
-    self.add_pawn_moves(buffer, temp_buffer, check_info.move_mask);
-    self.add_knight_moves(buffer, temp_buffer, check_info.move_mask);
-    self.add_bishop_moves(buffer, temp_buffer, check_info.move_mask);
-    self.add_rook_moves(buffer, temp_buffer, check_info.move_mask);
-    self.add_queen_moves(buffer, temp_buffer, check_info.move_mask);
-    self.add_king_moves(buffer, temp_buffer, safe_squares);
-
-    buffer.append(temp_buffer);
-    temp_buffer.clear();
-  }
-  pub(in super) fn collect_king_evasion(&self, buffer: &mut MoveBuffer, temp_buffer: &mut MoveBuffer) {
-    let safe_squares = self.get_safe_king_squares();
-
-    self.add_king_moves(buffer, temp_buffer, safe_squares);
-
-    buffer.append(&temp_buffer);
-    temp_buffer.clear();
+      if gives_check {
+        buffer.swap(kept, idx);
+        kept += 1;
+      }
+    }
+    buffer.truncate(kept);
   }
 }
\ No newline at end of file
@@ -2,36 +2,6 @@ use super::*;
 
 impl Board {
 
-  pub fn add_king_moves(&self, capture_buffer: &mut MoveBuffer, quiet_buffer: &mut MoveBuffer, move_mask: u64) {
-    let piece_index = 5 + self.side_to_move * 6;
-    let mut kings = self.bitboards[piece_index as usize];
-    let empty = !self.occupancy[2];
-    let opponents = self.occupancy[1 - self.side_to_move as usize];
-    while kings != 0 {
-      let from_sq = pop_lsb(&mut kings);
-      let move_map = self.get_pseudo_king_moves(from_sq) & move_mask;
-
-      let mut quiet_map = move_map & empty;
-      let mut capture_map = move_map & opponents;
-
-      while quiet_map != 0 {
-        let to_sq = pop_lsb(&mut quiet_map);
-        quiet_buffer.add(BitMove::quiet(
-          from_sq as u8,
-          to_sq as u8,
-          None
-        ));
-      }
-      while capture_map != 0 {
-        let to_sq = pop_lsb(&mut capture_map);
-        capture_buffer.add(BitMove::capture(
-          from_sq as u8,
-          to_sq as u8,
-          None
-        ));
-      }
-    }
-  }
   pub fn add_king_captures(&self, buffer: &mut MoveBuffer, move_mask: u64) {
     let offset = 6 * self.side_to_move as usize;
     let mut kings: u64 = self.bitboards[5 + offset];
@@ -51,38 +21,71 @@ impl Board {
       }
     }
   }
-  pub fn add_king_castles(&self, buffer: &mut MoveBuffer, move_mask: u64) {
+  pub fn add_king_quiets(&self, buffer: &mut MoveBuffer, move_mask: u64) {
+    let offset = 6 * self.side_to_move as usize;
+    let king_sq: u32 = self.bitboards[5 + offset].trailing_zeros();
+    let empty = !self.occupancy[2];
+    let mut quiets: u64 = self.get_pseudo_king_moves(king_sq) & empty & move_mask;
+
+    while quiets != 0 {
+      let to_sq = pop_lsb(&mut quiets);
+      buffer.add(BitMove::quiet(
+        king_sq as u8,
+        to_sq as u8,
+        None
+      ));
+    }
+  }
+  // works for chess960 too: every square the king and the rook pass or land on has to be empty (apart from
+  // the two of them) and the king may not pass through or land on an attacked square
+  pub fn add_king_castles(&self, buffer: &mut MoveBuffer) {
     if self.castling_rights & (0b11 << (2 - 2 * self.side_to_move)) == 0 {
       return;
     }
-    
+
     let offset = 5 + 6 * self.side_to_move as u8;
     let castle_offset = 2 - 2 * self.side_to_move as u8;
-    let castling_rights = self.castling_rights & 3 << castle_offset;
+    let back_rank = 56 * self.side_to_move as u32;
     let occupied = self.occupancy[2];
     let king_sq = self.bitboards[offset as usize].trailing_zeros();
-      
-    let queenside_mask = 0b111 << (king_sq - 3);
-    let kingside_mask = 0b11 << (king_sq + 1);
 
-    if (castling_rights & 1 << castle_offset) != 0
-        && queenside_mask & occupied == 0
-        && !move_mask & 0b11 << (king_sq - 2) == 0
-        && !self.is_square_attacked(king_sq - 2) {
-      buffer.add(BitMove::castle(
-        king_sq as u8,
-        (king_sq - 2) as u8
-      ));
-    }
-    if (castling_rights & 2 << castle_offset) != 0
-        && kingside_mask & occupied == 0
-        && !move_mask & 0b11 << (king_sq + 1) == 0
-        && !self.is_square_attacked(king_sq + 2) {
+    // (castling right, king destination, rook destination)
+    let castles: [(u8, u32, u32); 2] = [
+      (1 << castle_offset, back_rank + 2, back_rank + 3),
+      (2 << castle_offset, back_rank + 6, back_rank + 5)
+    ];
+
+    for (right, king_to, rook_to) in castles {
+      if self.castling_rights & right == 0 {
+        continue;
+      }
+      let rook_sq = self.castling_rook(right.trailing_zeros() as usize) as u32;
+      let castling_pieces = 1 << king_sq | 1 << rook_sq;
+      let king_path = SQUARES_BETWEEN[king_sq as usize][king_to as usize] | 1 << king_to;
+      let rook_path = SQUARES_BETWEEN[rook_sq as usize][rook_to as usize] | 1 << rook_to;
+
+      if (king_path | rook_path) & occupied & !castling_pieces != 0 {
+        continue;
+      }
+      // the safe king squares only cover the neighbours of the king and still count the castling rook as a
+      // blocker, so the path is checked square by square without the king and the rook on the board
+      let mut squares_to_check = king_path | 1 << king_sq;
+      let mut is_path_safe = true;
+      while squares_to_check != 0 {
+        let sq = pop_lsb(&mut squares_to_check);
+        if self.is_square_attacked(sq, occupied & !castling_pieces) {
+          is_path_safe = false;
+          break;
+        }
+      }
+      if !is_path_safe {
+        continue;
+      }
+
       buffer.add(BitMove::castle(
         king_sq as u8,
-        (king_sq + 2) as u8
+        king_to as u8
       ));
     }
-    
   }
-}
\ No newline at end of file
+}
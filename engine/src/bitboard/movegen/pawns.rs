@@ -69,8 +69,45 @@ impl Board {
       }
     }
   }
-  pub fn add_pawn_moves(&self, capture_buffer: &mut MoveBuffer, quiet_buffer: &mut MoveBuffer, move_mask: u64) {
-    self.add_pawn_captures(capture_buffer, move_mask);
-    self.add_pawn_quiets(quiet_buffer, move_mask);
+  pub fn add_pawn_en_passant(&self, buffer: &mut MoveBuffer, move_mask: u64) {
+    if self.en_passant_square == 0 {
+      return;
+    }
+
+    let offset = 6 * self.side_to_move as usize;
+    let to_sq = self.en_passant_square.trailing_zeros();
+    let captured_sq = if self.side_to_move == 0 { to_sq - 8 } else { to_sq + 8 };
+
+    // the move either has to block the check or capture the checking pawn
+    if move_mask & (1 << to_sq | 1 << captured_sq) == 0 {
+      return;
+    }
+
+    let mut pawns: u64 = self.get_pseudo_opponent_pawn_captures(to_sq) & self.bitboards[offset];
+    while pawns != 0 {
+      let from_sq = pop_lsb(&mut pawns);
+
+      if self.get_pin_masked_moves(1 << to_sq, from_sq) == 0 {
+        continue;
+      }
+
+      // both pawns leave their squares at once, which can open a line towards the king
+      // that no pin was recorded for (e.g. king and rook on the same rank as the two pawns)
+      let king_sq = self.bitboards[5 + offset].trailing_zeros() as usize;
+      let occupancy = (self.occupancy[2] & !(1 << from_sq | 1 << captured_sq)) | 1 << to_sq;
+      let queen_rook_mask = self.bitboards[10 - offset] | self.bitboards[9 - offset];
+      let queen_bishop_mask = self.bitboards[10 - offset] | self.bitboards[8 - offset];
+
+      let is_exposed = ROOK_MAGICS.get_attacks(king_sq, occupancy) & queen_rook_mask != 0
+                    || BISHOP_MAGICS.get_attacks(king_sq, occupancy) & queen_bishop_mask != 0;
+      if is_exposed {
+        continue;
+      }
+
+      buffer.add(BitMove::en_passant(
+        from_sq as u8,
+        to_sq as u8
+      ));
+    }
   }
 }
\ No newline at end of file
@@ -0,0 +1,82 @@
+use super::board::Board;
+use crate::movefilter::MoveFilter;
+
+impl Board {
+
+  pub fn perft(&mut self, depth: u32) -> u64 {
+    if depth == 0 {
+      return 1;
+    }
+
+    let moves = self.legal_moves(MoveFilter::All);
+
+    // bulk counting, the leaf moves do not have to be played
+    if depth == 1 {
+      return moves.len() as u64;
+    }
+
+    let mut nodes = 0u64;
+    for next_move in moves {
+      self.make_move(&next_move);
+      nodes += self.perft(depth - 1);
+      self.unmake_move();
+    }
+    return nodes;
+  }
+}
+
+
+// <----- TESTS ----->
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn assert_perft(fen: &str, expected_nodes: &[u64]) {
+    let mut board = Board::build(fen);
+    for (depth, expected) in expected_nodes.iter().enumerate() {
+      assert_eq!(board.perft(depth as u32 + 1), *expected, "perft({}) failed for {}", depth + 1, fen);
+      assert_eq!(board.fen(), fen);
+    }
+  }
+
+  #[test]
+  fn perft_start_position_test() {
+    assert_perft("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", &[20, 400, 8902, 197281, 4865609]);
+  }
+
+  #[test]
+  fn perft_kiwipete_test() {
+    assert_perft("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", &[48, 2039, 97862, 4085603]);
+  }
+
+  #[test]
+  fn perft_en_passant_pins_test() {
+    assert_perft("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", &[14, 191, 2812, 43238, 674624]);
+  }
+
+  #[test]
+  fn perft_promotions_test() {
+    assert_perft("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", &[6, 264, 9467, 422333]);
+    assert_perft("r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1", &[6, 264, 9467, 422333]);
+  }
+
+  #[test]
+  fn perft_castling_test() {
+    assert_perft("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", &[44, 1486, 62379, 2103487]);
+  }
+
+  #[test]
+  fn perft_middlegame_test() {
+    assert_perft("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10", &[46, 2079, 89890, 3894594]);
+  }
+
+  #[test]
+  fn perft_chess960_test() {
+    assert_perft("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9", &[21, 528, 12189, 326672]);
+    assert_perft("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w KQkq - 1 9", &[21, 807, 18002, 667366]);
+    assert_perft("b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w KQ - 1 9", &[20, 479, 10471, 273318]);
+    assert_perft("qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w kq - 0 9", &[22, 593, 13440, 382958]);
+    assert_perft("1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w KQkq - 0 9", &[28, 1120, 31058, 1171749]);
+  }
+}
@@ -0,0 +1,234 @@
+use super::board::Board;
+use super::bitmove::{BitMove, BitMoveType};
+use super::movebuffer::MoveBuffer;
+use super::utils::*;
+use crate::movefilter::MoveFilter;
+use crate::sanerror::SanError;
+
+impl Board {
+
+  // the move has to be legal in the current position
+  pub fn san(&mut self, bitmove: &BitMove) -> String {
+    let mut buffer = MoveBuffer::new();
+    self.generate_moves(&mut buffer, MoveFilter::All);
+
+    let from_sq = bitmove.from_square();
+    let to_sq = bitmove.to_square();
+    let piece = self.piece_board(from_sq);
+    let is_capture = matches!(bitmove.move_type(), BitMoveType::Capture | BitMoveType::EnPassant);
+    let mut san = String::new();
+
+    if bitmove.move_type() == BitMoveType::Castle {
+      san.push_str(if to_sq % 8 == 6 { "O-O" } else { "O-O-O" });
+    }
+    else if piece % 6 == 0 {
+      if is_capture {
+        san.push(notation_from_square_number(from_sq).chars().next().unwrap());
+        san.push('x');
+      }
+      san.push_str(&notation_from_square_number(to_sq));
+      if let Some(promotion_piece) = bitmove.promotion_piece() {
+        san.push('=');
+        san.push(get_character_by_piece_id(promotion_piece));
+      }
+    }
+    else {
+      san.push(get_character_by_piece_id(piece % 6));
+
+      // other pieces of the same kind that can reach the same square
+      let rivals: Vec<u8> = buffer.contents().iter()
+        .filter(|other| other.to_square() == to_sq && other.from_square() != from_sq)
+        .filter(|other| self.piece_board(other.from_square()) == piece)
+        .map(|other| other.from_square())
+        .collect();
+      if !rivals.is_empty() {
+        let from_notation = notation_from_square_number(from_sq);
+        let shares_file = rivals.iter().any(|sq| sq % 8 == from_sq % 8);
+        let shares_rank = rivals.iter().any(|sq| sq / 8 == from_sq / 8);
+
+        if !shares_file {
+          san.push_str(&from_notation[0..1]);
+        }
+        else if !shares_rank {
+          san.push_str(&from_notation[1..2]);
+        }
+        else {
+          san.push_str(&from_notation);
+        }
+      }
+
+      if is_capture {
+        san.push('x');
+      }
+      san.push_str(&notation_from_square_number(to_sq));
+    }
+
+    self.make_move(bitmove);
+    let is_check = self.generate_moves(&mut buffer, MoveFilter::All);
+    if is_check {
+      san.push(if buffer.count() == 0 { '#' } else { '+' });
+    }
+    self.unmake_move();
+
+    return san;
+  }
+
+  // accepts the usual variations too: 0-0 castling, missing '=' before the promotion piece,
+  // missing or extra check marks and trailing annotations like ! and ?
+  pub fn parse_san(&mut self, san: &str) -> Result<BitMove, SanError> {
+    let mut buffer = MoveBuffer::new();
+    self.generate_moves(&mut buffer, MoveFilter::All);
+
+    let trimmed = san.trim().trim_end_matches(['+', '#', '!', '?']);
+    let invalid = || SanError::InvalidSan(san.to_string());
+
+    let castle = match trimmed {
+      "O-O" | "0-0" => Some(true),
+      "O-O-O" | "0-0-0" => Some(false),
+      _ => None
+    };
+    if let Some(is_kingside) = castle {
+      return buffer.contents().iter()
+        .find(|bitmove| bitmove.move_type() == BitMoveType::Castle
+          && (bitmove.to_square() % 8 == 6) == is_kingside)
+        .copied()
+        .ok_or_else(|| SanError::IllegalMove(san.to_string()));
+    }
+
+    let mut chars: Vec<char> = trimmed.chars().collect();
+
+    let piece_type = match chars.first() {
+      Some('N') => 1,
+      Some('B') => 2,
+      Some('R') => 3,
+      Some('Q') => 4,
+      Some('K') => 5,
+      Some(_) => 0,
+      None => return Err(invalid())
+    };
+    if piece_type != 0 {
+      chars.remove(0);
+    }
+
+    let mut promotion_piece: Option<u8> = None;
+    if piece_type == 0 {
+      if let Some(&last) = chars.last() {
+        promotion_piece = match last.to_ascii_uppercase() {
+          'N' => Some(1),
+          'B' => Some(2),
+          'R' => Some(3),
+          'Q' => Some(4),
+          _ => None
+        };
+      }
+      if promotion_piece.is_some() {
+        chars.pop();
+        if chars.last() == Some(&'=') {
+          chars.pop();
+        }
+      }
+    }
+
+    if chars.len() < 2 {
+      return Err(invalid());
+    }
+    let target: String = chars[chars.len() - 2..].iter().collect();
+    let to_sq = try_get_square_number_from_notation(&target).map_err(|_| invalid())?;
+
+    // whatever is left between the piece and the target square narrows down the origin
+    let mut from_file: Option<u8> = None;
+    let mut from_rank: Option<u8> = None;
+    let mut is_capture = false;
+    for c in chars[..chars.len() - 2].iter() {
+      match c {
+        'a'..='h' if from_file.is_none() => from_file = Some(*c as u8 - b'a'),
+        '1'..='8' if from_rank.is_none() => from_rank = Some(*c as u8 - b'1'),
+        'x' | ':' if !is_capture => is_capture = true,
+        _ => return Err(invalid())
+      }
+    }
+
+    let side_offset = 6 * self.side_to_move();
+    let candidates: Vec<BitMove> = buffer.contents().iter()
+      .filter(|bitmove| bitmove.move_type() != BitMoveType::Castle)
+      .filter(|bitmove| bitmove.to_square() == to_sq)
+      .filter(|bitmove| self.piece_board(bitmove.from_square()) == piece_type + side_offset)
+      .filter(|bitmove| bitmove.promotion_piece() == promotion_piece)
+      .filter(|bitmove| from_file.is_none_or(|file| bitmove.from_square() % 8 == file))
+      .filter(|bitmove| from_rank.is_none_or(|rank| bitmove.from_square() / 8 == rank))
+      .copied()
+      .collect();
+
+    return match candidates.len() {
+      0 => Err(SanError::IllegalMove(san.to_string())),
+      1 => Ok(candidates[0]),
+      _ => Err(SanError::AmbiguousMove(san.to_string()))
+    };
+  }
+}
+
+
+// <----- TESTS ----->
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn assert_san(fen: &str, uci_move: &str, expected_san: &str) {
+    let mut board = Board::build(fen);
+    let bitmove = board.legal_moves(MoveFilter::All)
+      .find(|bitmove| bitmove.uci_notation() == uci_move)
+      .unwrap();
+    assert_eq!(board.san(&bitmove), expected_san, "{} in {}", uci_move, fen);
+    assert_eq!(board.fen(), fen);
+
+    let parsed = board.parse_san(expected_san).unwrap();
+    assert_eq!(parsed.uci_notation(), uci_move, "{} in {}", expected_san, fen);
+  }
+
+  #[test]
+  fn san_test() {
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    assert_san(start, "e2e4", "e4");
+    assert_san(start, "g1f3", "Nf3");
+
+    let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    assert_san(kiwipete, "e1g1", "O-O");
+    assert_san(kiwipete, "e1c1", "O-O-O");
+    assert_san(kiwipete, "d5e6", "dxe6");
+    assert_san(kiwipete, "e5f7", "Nxf7");
+    assert_san(kiwipete, "f3f6", "Qxf6");
+    assert_san(kiwipete, "e2a6", "Bxa6");
+    assert_san(kiwipete, "g2h3", "gxh3");
+
+    // disambiguation by file, by rank and by both
+    assert_san("4k3/8/8/8/8/8/4K3/R6R w - - 0 1", "a1d1", "Rad1");
+    assert_san("4k3/8/R7/8/8/8/8/R3K3 w - - 0 1", "a1a3", "R1a3");
+    assert_san("4k3/8/8/8/8/Q7/8/Q1Q1K3 w - - 0 1", "a1b2", "Qa1b2");
+
+    // promotions, en passant and check marks
+    assert_san("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8q", "b8=Q+");
+    assert_san("2r1k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7c8n", "bxc8=N");
+    assert_san("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6", "exd6");
+    assert_san("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "a1a8", "Ra8#");
+  }
+
+  #[test]
+  fn parse_san_test() {
+    let mut board = Board::build("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+
+    assert_eq!(board.parse_san("0-0").unwrap().uci_notation(), "e1g1");
+    assert_eq!(board.parse_san("Nxf7!?").unwrap().uci_notation(), "e5f7");
+    assert_eq!(board.parse_san("Nf7").unwrap().uci_notation(), "e5f7");
+    assert_eq!(board.parse_san("Qf3f6+").unwrap().uci_notation(), "f3f6");
+
+    assert_eq!(board.parse_san("Nb6").unwrap_err(), SanError::IllegalMove("Nb6".to_string()));
+    assert_eq!(board.parse_san("d8").unwrap_err(), SanError::IllegalMove("d8".to_string()));
+    assert_eq!(board.parse_san("Nz9").unwrap_err(), SanError::InvalidSan("Nz9".to_string()));
+    assert_eq!(board.parse_san("").unwrap_err(), SanError::InvalidSan("".to_string()));
+
+    let mut board = Board::build("4k3/8/8/8/8/8/4K3/R6R w - - 0 1");
+    assert_eq!(board.parse_san("Rd1").unwrap_err(), SanError::AmbiguousMove("Rd1".to_string()));
+    assert_eq!(board.parse_san("Rhf1").unwrap().uci_notation(), "h1f1");
+  }
+}
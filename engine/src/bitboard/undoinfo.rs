@@ -0,0 +1,12 @@
+use super::bitmove::BitMove;
+
+#[derive(Copy, Clone)]
+pub struct UndoInfo {
+  pub played_move: BitMove,
+  pub captured_piece: u8,     // 12 -> nothing was captured
+  pub castling_rights: u8,
+  pub en_passant_square: u64,
+  pub halfmove_clock: u16,
+  pub fullmove_number: u16,
+  pub hash: u64
+}
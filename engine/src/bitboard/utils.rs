@@ -28,24 +28,27 @@ pub fn notation_from_square_number(sq: u8) -> String {
 }
 
 pub fn try_get_square_number_from_notation(notation: &str) -> Result<u8, ()> {
-
-  let file = match notation.chars().nth(0).unwrap() {
-    'a' => 0,
-    'b' => 1,
-    'c' => 2,
-    'd' => 3,
-    'e' => 4,
-    'f' => 5,
-    'g' => 6,
-    'h' => 7,
+  let mut chars = notation.chars();
+
+  let file = match chars.next() {
+    Some('a') => 0,
+    Some('b') => 1,
+    Some('c') => 2,
+    Some('d') => 3,
+    Some('e') => 4,
+    Some('f') => 5,
+    Some('g') => 6,
+    Some('h') => 7,
      _  => { return Result::Err(()); }
   };
-  if let Some(rank) = notation.chars().nth(1) {
-    return Result::Ok(file + 8 * (rank.to_digit(10).unwrap() as u8) - 8);
-  }
-  else {
+  let rank = match chars.next() {
+    Some(rank) if ('1'..='8').contains(&rank) => rank.to_digit(10).unwrap() as u8,
+     _  => { return Result::Err(()); }
+  };
+  if chars.next().is_some() {
     return Result::Err(());
   }
+  return Result::Ok(file + 8 * rank - 8);
 }
 
 const PIECE_CHARACTERS: [char; 12] = ['P', 'N', 'B', 'R', 'Q', 'K', 'p', 'n', 'b', 'r', 'q', 'k'];
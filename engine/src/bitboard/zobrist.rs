@@ -0,0 +1,118 @@
+use once_cell::sync::Lazy;
+
+use super::board::Board;
+
+pub struct ZobristKeys {
+  pub pieces: [[u64; 64]; 12],
+  pub castling: [u64; 16],
+  pub en_passant: [u64; 8],
+  pub side: u64
+}
+
+// ZOBRIST_KEYS, filled from a fixed seed so hashes are the same across runs and builds
+pub static ZOBRIST_KEYS: Lazy<ZobristKeys> = Lazy::new(|| {
+  let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+  let mut next_key = || {
+    // xorshift64*
+    state ^= state >> 12;
+    state ^= state << 25;
+    state ^= state >> 27;
+    return state.wrapping_mul(0x2545_F491_4F6C_DD1D);
+  };
+
+  let mut keys = ZobristKeys {
+    pieces: [[0u64; 64]; 12],
+    castling: [0u64; 16],
+    en_passant: [0u64; 8],
+    side: 0u64
+  };
+  for piece in 0..12 {
+    for sq in 0..64 {
+      keys.pieces[piece][sq] = next_key();
+    }
+  }
+  for rights in 0..16 {
+    keys.castling[rights] = next_key();
+  }
+  for file in 0..8 {
+    keys.en_passant[file] = next_key();
+  }
+  keys.side = next_key();
+
+  return keys;
+});
+
+impl Board {
+
+  pub fn calc_hash(&self) -> u64 {
+    let mut hash = 0u64;
+
+    for sq in 0..64 {
+      let piece = self.piece_board[sq];
+      if piece != Self::EMPTY_SQUARE {
+        hash ^= ZOBRIST_KEYS.pieces[piece as usize][sq];
+      }
+    }
+    hash ^= ZOBRIST_KEYS.castling[self.castling_rights as usize];
+    if self.en_passant_square != 0 {
+      hash ^= ZOBRIST_KEYS.en_passant[self.en_passant_square.trailing_zeros() as usize % 8];
+    }
+    if self.side_to_move == 1 {
+      hash ^= ZOBRIST_KEYS.side;
+    }
+
+    return hash;
+  }
+}
+
+
+// <----- TESTS ----->
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::bitmove::BitMove;
+
+  #[test]
+  fn incremental_hash_test() {
+
+    // 1. Nf3 Nf6 2. Nc3 Nc6 and 1. Nc3 Nc6 2. Nf3 Nf6 reach the same position
+    let mut first_order = Board::new();
+    let mut second_order = Board::new();
+    let first_moves = [(6, 21), (62, 45), (1, 18), (57, 42)];
+    let second_moves = [(1, 18), (57, 42), (6, 21), (62, 45)];
+
+    for idx in 0..4 {
+      first_order.make_move(&BitMove::quiet(first_moves[idx].0, first_moves[idx].1, None));
+      second_order.make_move(&BitMove::quiet(second_moves[idx].0, second_moves[idx].1, None));
+    }
+    assert_eq!(first_order.hash(), second_order.hash());
+    assert_eq!(first_order.hash(), first_order.calc_hash());
+    assert_ne!(first_order.hash(), Board::new().hash());
+
+    first_order.unmake_move();
+    assert_ne!(first_order.hash(), second_order.hash());
+    assert_eq!(first_order.hash(), first_order.calc_hash());
+  }
+
+  #[test]
+  fn hash_state_test() {
+
+    let fens = [
+      "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+      "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 1",
+      "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b Kkq - 0 1",
+      "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+    ];
+
+    let hashes: Vec<u64> = fens.iter().map(|fen| Board::build(fen).hash()).collect();
+    for first in 0..4 {
+      for second in (first + 1)..4 {
+        assert_ne!(hashes[first], hashes[second]);
+      }
+    }
+
+    // the move counters are not part of the position
+    assert_eq!(hashes[0], Board::build("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 7 30").hash());
+  }
+}
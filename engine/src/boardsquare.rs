@@ -1,6 +1,7 @@
+use log::warn;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct BoardSquare {
     pub x: usize,
     pub y: usize,
@@ -15,14 +16,10 @@ impl BoardSquare {
         #[cfg(debug_assertions)]
         {
             if x > 7 {
-                println!(
-                    "Warning: x coordinate of square is bigger than 7, it might not be on the board!"
-                );
+                warn!("x coordinate of square is bigger than 7, it might not be on the board!");
             }
             if y > 7 {
-                println!(
-                    "Warning: y coordinate of square is bigger than 7, it might not be on the board!"
-                );
+                warn!("y coordinate of square is bigger than 7, it might not be on the board!");
             }
         }
         return Self { x: x, y: y };
@@ -35,7 +32,7 @@ impl BoardSquare {
         #[cfg(debug_assertions)]
         {
             if !(0..8).contains(&rank) {
-                println!("Warning: internal engine issue, given index is not on the board!");
+                warn!("internal engine issue, given index is not on the board!");
             }
         }
 
@@ -44,6 +41,10 @@ impl BoardSquare {
             y: rank as usize,
         };
     }
+    pub fn is_on_board(&self) -> bool {
+        return self.x < 8 && self.y < 8;
+    }
+
     pub(super) fn to_index(&self) -> u8 {
         return (8 * self.y + self.x) as u8;
     }
@@ -4,7 +4,10 @@ use crate::bitboard::{
 };
 
 use super::boardsquare::BoardSquare;
+use super::illegalmove::IllegalMove;
+use super::movefilter::MoveFilter;
 use super::piecetype::PieceType;
+use super::sanerror::SanError;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -97,6 +100,22 @@ impl ChessMove {
         };
     }
 
+    pub fn en_passant(
+        pawn_type: PieceType,
+        from_square: BoardSquare,
+        to_square: BoardSquare,
+        captured_piece: PieceType,
+        captured_from: BoardSquare,
+    ) -> Self {
+        return Self::EnPassant {
+            pawn_type,
+            from_square,
+            to_square,
+            captured_piece,
+            captured_from,
+        };
+    }
+
     pub(super) fn from_bitmove(bitmove: &BitMove, board: &Board) -> Self {
         match bitmove.move_type() {
             BitMoveType::Quiet => {
@@ -105,7 +124,7 @@ impl ChessMove {
                 let from_square = BoardSquare::from_index(from_square_index);
                 let to_square = BoardSquare::from_index(bitmove.to_square());
                 let promotion_piece = match bitmove.promotion_piece() {
-                    Some(piece) => Some(PieceType::from_index(piece)),
+                    Some(piece) => Some(PieceType::from_index(piece + 6 * board.side_to_move())),
                     None => None,
                 };
 
@@ -124,7 +143,7 @@ impl ChessMove {
                 let to_square = BoardSquare::from_index(to_square_index);
                 let captured_piece = PieceType::from_index(board.piece_board(to_square_index));
                 let promotion_piece = match bitmove.promotion_piece() {
-                    Some(piece) => Some(PieceType::from_index(piece)),
+                    Some(piece) => Some(PieceType::from_index(piece + 6 * board.side_to_move())),
                     None => None,
                 };
 
@@ -147,18 +166,9 @@ impl ChessMove {
                 } else {
                     PieceType::BlackRook
                 };
-                let rook_from_index = if bitmove.to_square() > bitmove.from_square() {
-                    bitmove.from_square() + 3
-                } else {
-                    bitmove.from_square() - 4
-                };
-                let rook_from = BoardSquare::from_index(rook_from_index);
-                let rook_to_index = if bitmove.to_square() > bitmove.from_square() {
-                    bitmove.from_square() + 1
-                } else {
-                    bitmove.from_square() - 1
-                };
-                let rook_to = BoardSquare::from_index(rook_to_index);
+                let (rook_from_index, rook_to_index) = board.castle_rook_squares(bitmove);
+                let rook_from = BoardSquare::from_index(rook_from_index as u8);
+                let rook_to = BoardSquare::from_index(rook_to_index as u8);
 
                 return ChessMove::Castle {
                     king_type,
@@ -170,7 +180,26 @@ impl ChessMove {
                 };
             }
             BitMoveType::EnPassant => {
-                panic!("ChessMove::from_bitmove was left unimplemented");
+                let from_square_index = bitmove.from_square();
+                let to_square_index = bitmove.to_square();
+                let pawn_type = PieceType::from_index(board.piece_board(from_square_index));
+                let from_square = BoardSquare::from_index(from_square_index);
+                let to_square = BoardSquare::from_index(to_square_index);
+                let captured_from_index = if board.side_to_move() == 0 {
+                    to_square_index - 8
+                } else {
+                    to_square_index + 8
+                };
+                let captured_piece = PieceType::from_index(board.piece_board(captured_from_index));
+                let captured_from = BoardSquare::from_index(captured_from_index);
+
+                return ChessMove::EnPassant {
+                    pawn_type,
+                    from_square,
+                    to_square,
+                    captured_piece,
+                    captured_from,
+                };
             }
         }
     }
@@ -183,7 +212,7 @@ impl ChessMove {
                 promotion_piece,
             } => {
                 let promotion_piece = match promotion_piece {
-                    Some(piece) => Some(piece.to_index()),
+                    Some(piece) => Some(piece.to_index() % 6),
                     None => None,
                 };
                 return BitMove::quiet(
@@ -200,7 +229,7 @@ impl ChessMove {
                 promotion_piece,
             } => {
                 let promotion_piece = match promotion_piece {
-                    Some(piece) => Some(piece.to_index()),
+                    Some(piece) => Some(piece.to_index() % 6),
                     None => None,
                 };
                 return BitMove::capture(
@@ -226,13 +255,168 @@ impl ChessMove {
                 captured_piece,
                 captured_from,
             } => {
-                panic!("ChessMove::to_bitmove was left unimplemented");
+                return BitMove::en_passant(from_square.to_index(), to_square.to_index());
             }
         };
         return bitmove;
     }
 
+    fn squares(&self) -> Vec<&BoardSquare> {
+        return match self {
+            ChessMove::Quiet {
+                from_square,
+                to_square,
+                ..
+            }
+            | ChessMove::Capture {
+                from_square,
+                to_square,
+                ..
+            } => vec![from_square, to_square],
+            ChessMove::Castle {
+                king_from,
+                king_to,
+                rook_from,
+                rook_to,
+                ..
+            } => vec![king_from, king_to, rook_from, rook_to],
+            ChessMove::EnPassant {
+                from_square,
+                to_square,
+                captured_from,
+                ..
+            } => vec![from_square, to_square, captured_from],
+        };
+    }
+
+    // checks the move against the legal moves of the board, including the pieces it names, and
+    // returns the matching internal move
+    pub(super) fn validate_on_board(&self, board: &mut Board) -> Result<BitMove, IllegalMove> {
+        // the squares come from the client, they must not reach the index arithmetic unchecked
+        if let Some(square) = self
+            .squares()
+            .into_iter()
+            .find(|square| !square.is_on_board())
+        {
+            return Err(IllegalMove::OffBoard(square.clone()));
+        }
+        let bitmove = self.to_bitmove();
+        let notation = bitmove.uci_notation();
+        let moved_piece = board.piece_board(bitmove.from_square());
+
+        if moved_piece == Board::EMPTY_SQUARE {
+            return Err(IllegalMove::EmptySquare(notation));
+        }
+        if moved_piece / 6 != board.side_to_move() {
+            return Err(IllegalMove::OpponentPiece(notation));
+        }
+
+        let (piece_type, promotion_piece) = match self {
+            ChessMove::Quiet {
+                piece_type,
+                promotion_piece,
+                ..
+            } => (piece_type, promotion_piece.as_ref()),
+            ChessMove::Capture {
+                piece_type,
+                to_square,
+                captured_piece,
+                promotion_piece,
+                ..
+            } => {
+                if captured_piece.to_index() != board.piece_board(to_square.to_index()) {
+                    return Err(IllegalMove::WrongCapturedPiece(notation));
+                }
+                (piece_type, promotion_piece.as_ref())
+            }
+            ChessMove::Castle {
+                king_type,
+                rook_type,
+                rook_from,
+                rook_to,
+                ..
+            } => {
+                let (rook_from_index, rook_to_index) = board.castle_rook_squares(&bitmove);
+                if rook_type.to_index() != 3 + 6 * board.side_to_move() {
+                    return Err(IllegalMove::WrongPieceType(notation));
+                }
+                if rook_from.to_index() as usize != rook_from_index
+                    || rook_to.to_index() as usize != rook_to_index
+                {
+                    return Err(IllegalMove::NotLegal(notation));
+                }
+                (king_type, None)
+            }
+            ChessMove::EnPassant {
+                pawn_type,
+                to_square,
+                captured_piece,
+                captured_from,
+                ..
+            } => {
+                let expected_from = if board.side_to_move() == 0 {
+                    to_square.to_index().wrapping_sub(8)
+                } else {
+                    to_square.to_index() + 8
+                };
+                if captured_from.to_index() != expected_from
+                    || captured_piece.to_index() != 6 - 6 * board.side_to_move()
+                {
+                    return Err(IllegalMove::WrongCapturedPiece(notation));
+                }
+                (pawn_type, None)
+            }
+        };
+
+        if piece_type.to_index() != moved_piece {
+            return Err(IllegalMove::WrongPieceType(notation));
+        }
+        if let Some(promotion_piece) = promotion_piece
+            && promotion_piece.to_index() / 6 != board.side_to_move()
+        {
+            return Err(IllegalMove::WrongPromotion(notation));
+        }
+
+        let mut same_squares = false;
+        for legal_move in board.legal_moves(MoveFilter::All) {
+            if legal_move == bitmove {
+                return Ok(bitmove);
+            }
+            if legal_move.move_type() == bitmove.move_type()
+                && legal_move.from_square() == bitmove.from_square()
+                && legal_move.to_square() == bitmove.to_square()
+            {
+                same_squares = true;
+            }
+        }
+
+        // the only difference to a legal move is the promotion (missing, pawn or king)
+        if same_squares {
+            return Err(IllegalMove::WrongPromotion(notation));
+        }
+        return Err(IllegalMove::NotLegal(notation));
+    }
+
     pub fn notation(&self) -> String {
         return self.to_bitmove().uci_notation();
     }
+
+    // standard algebraic notation (e.g. Nbd7, exd6, O-O, e8=Q+) of the move played from fen,
+    // the move has to be legal there
+    pub fn to_san(&self, fen: &str) -> Result<String, IllegalMove> {
+        let mut board = Board::try_from_fen(fen)?;
+        let bitmove = self.validate_on_board(&mut board)?;
+        return Ok(board.san(&bitmove));
+    }
+
+    // resolves a move in standard algebraic notation to the legal move it describes in fen
+    pub fn from_san(san: &str, fen: &str) -> Result<Self, SanError> {
+        let mut board = Board::try_from_fen(fen)?;
+        return Self::from_san_on_board(san, &mut board);
+    }
+
+    pub(super) fn from_san_on_board(san: &str, board: &mut Board) -> Result<Self, SanError> {
+        let bitmove = board.parse_san(san)?;
+        return Ok(Self::from_bitmove(&bitmove, board));
+    }
 }
@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub enum FenError {
+    MissingField(String),
+    InvalidPiecePlacement(char),
+    RankOverflow(usize),
+    IncompleteRank(usize),
+    InvalidRankCount(usize),
+    InvalidSideToMove(String),
+    InvalidCastling(String),
+    InvalidEnPassant(String),
+    InvalidHalfmoveClock(String),
+    InvalidFullmoveNumber(String),
+    MissingKing(String),
+    ExtraKings(String),
+    OpponentInCheck,
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            FenError::MissingField(field) => write!(f, "fen is missing the {} field", field),
+            FenError::InvalidPiecePlacement(c) => {
+                write!(f, "invalid character '{}' in piece placement", c)
+            }
+            FenError::RankOverflow(rank) => write!(f, "rank {} has more than 8 squares", rank),
+            FenError::IncompleteRank(rank) => write!(f, "rank {} has less than 8 squares", rank),
+            FenError::InvalidRankCount(count) => {
+                write!(f, "piece placement has {} ranks instead of 8", count)
+            }
+            FenError::InvalidSideToMove(side) => write!(f, "invalid side to move '{}'", side),
+            FenError::InvalidCastling(castling) => {
+                write!(f, "invalid castling availability '{}'", castling)
+            }
+            FenError::InvalidEnPassant(square) => {
+                write!(f, "invalid en passant square '{}'", square)
+            }
+            FenError::InvalidHalfmoveClock(clock) => write!(f, "invalid halfmove clock '{}'", clock),
+            FenError::InvalidFullmoveNumber(number) => {
+                write!(f, "invalid fullmove number '{}'", number)
+            }
+            FenError::MissingKing(color) => write!(f, "{} has no king", color),
+            FenError::ExtraKings(color) => write!(f, "{} has more than one king", color),
+            FenError::OpponentInCheck => write!(f, "the side not to move is in check"),
+        };
+    }
+}
+
+impl std::error::Error for FenError {}
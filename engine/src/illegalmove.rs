@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::boardsquare::BoardSquare;
+use super::fenerror::FenError;
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub enum IllegalMove {
+    InvalidFen(FenError),
+    OffBoard(BoardSquare),
+    EmptySquare(String),
+    OpponentPiece(String),
+    WrongPieceType(String),
+    WrongCapturedPiece(String),
+    WrongPromotion(String),
+    NotLegal(String),
+}
+
+impl fmt::Display for IllegalMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            IllegalMove::InvalidFen(e) => write!(f, "invalid fen: {}", e),
+            IllegalMove::OffBoard(square) => {
+                write!(f, "square ({}, {}) is not on the board", square.x, square.y)
+            }
+            IllegalMove::EmptySquare(uci) => {
+                write!(f, "'{}' starts from a square without a piece", uci)
+            }
+            IllegalMove::OpponentPiece(uci) => {
+                write!(f, "'{}' moves a piece of the side not to move", uci)
+            }
+            IllegalMove::WrongPieceType(uci) => {
+                write!(f, "'{}' names a different piece than the one on its square", uci)
+            }
+            IllegalMove::WrongCapturedPiece(uci) => {
+                write!(f, "'{}' names a different captured piece than the one on the board", uci)
+            }
+            IllegalMove::WrongPromotion(uci) => {
+                write!(f, "'{}' is missing or has an invalid promotion piece", uci)
+            }
+            IllegalMove::NotLegal(uci) => write!(f, "'{}' is not a legal move", uci),
+        };
+    }
+}
+
+impl std::error::Error for IllegalMove {}
+
+impl From<FenError> for IllegalMove {
+    fn from(e: FenError) -> Self {
+        return IllegalMove::InvalidFen(e);
+    }
+}
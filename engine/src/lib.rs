@@ -1,62 +1,255 @@
 mod bitboard;
 pub mod boardsquare;
 pub mod chessmove;
+pub mod fenerror;
 pub mod gameend;
+pub mod illegalmove;
+pub mod movefilter;
 pub mod movetype;
+pub mod pgn;
+pub mod pgnerror;
 pub mod piecetype;
+pub mod position;
+pub mod sanerror;
+pub mod search;
 
+use std::time::Instant;
+
+use log::debug;
+
+use bitboard::bitmove::BitMoveType;
 use bitboard::board::Board;
 use bitboard::movebuffer::MoveBuffer;
 use chessmove::ChessMove;
+use fenerror::FenError;
 use gameend::GameEnd;
+use illegalmove::IllegalMove;
+use movefilter::MoveFilter;
+use search::{SearchLimits, SearchResult, Searcher};
+
+pub fn get_available_moves(fen: &str) -> Result<Vec<ChessMove>, FenError> {
+    let start = Instant::now();
+    let mut board = Board::try_from_fen(fen)?;
+    let generated_moves: Vec<ChessMove> = board
+        .legal_moves(MoveFilter::All)
+        .map(|bitmove| ChessMove::from_bitmove(&bitmove, &board))
+        .collect();
+
+    debug!(
+        "get_available_moves resulted in {} moves in {:?}",
+        generated_moves.len(),
+        start.elapsed()
+    );
+    return Ok(generated_moves);
+}
+
+pub fn is_game_over(fen: &str) -> Result<Option<GameEnd>, FenError> {
+    return is_game_over_with_history(fen, &[]);
+}
 
-pub fn get_available_moves(fen: &str) -> Vec<ChessMove> {
-    let mut board = Board::build(fen);
+// history holds the fen of every position that occurred before the current one
+pub fn is_game_over_with_history(
+    fen: &str,
+    history: &[String],
+) -> Result<Option<GameEnd>, FenError> {
+    let start = Instant::now();
+    let game_end = get_game_end(fen, history)?;
+
+    debug!("is_game_over answered {:?} in {:?}", game_end, start.elapsed());
+    return Ok(game_end);
+}
+
+fn get_game_end(fen: &str, history: &[String]) -> Result<Option<GameEnd>, FenError> {
+    let mut board = Board::try_from_fen(fen)?;
     let mut buffer = MoveBuffer::new();
-    let mut temp_buffer = MoveBuffer::new();
-    let mut generated_moves: Vec<ChessMove> = vec![];
+    let in_check = board.generate_moves(&mut buffer, MoveFilter::All);
+
+    if buffer.count() == 0 {
+        if !in_check {
+            return Ok(Some(GameEnd::Draw("Stalemate".to_string())));
+        }
+        return if board.side_to_move() == 0 {
+            Ok(Some(GameEnd::BlackWon("Checkmate".to_string())))
+        } else {
+            Ok(Some(GameEnd::WhiteWon("Checkmate".to_string())))
+        };
+    }
+    if board.is_insufficient_material() {
+        return Ok(Some(GameEnd::Draw("Insufficient material".to_string())));
+    }
+    if board.halfmove_clock() >= 150 {
+        return Ok(Some(GameEnd::Draw("Seventy-five-move rule".to_string())));
+    }
+    if count_repetitions(fen, &mut board, history)? >= 5 {
+        return Ok(Some(GameEnd::Draw("Fivefold repetition".to_string())));
+    }
+    return Ok(None);
+}
+
+// whether the given side has enough material to ever checkmate, a player who runs out of time
+// against an opponent without it gets a draw instead of a loss
+pub fn has_mating_material(fen: &str, white: bool) -> Result<bool, FenError> {
+    let board = Board::try_from_fen(fen)?;
+    return Ok(board.has_mating_material(if white { 0 } else { 1 }));
+}
 
-    board.collect_moves(&mut buffer, &mut temp_buffer);
+// draws that only end the game when one of the players claims them
+pub fn get_claimable_draw(fen: &str, history: &[String]) -> Result<Option<GameEnd>, FenError> {
+    let mut board = Board::try_from_fen(fen)?;
 
-    for idx in 0..buffer.count() {
-        generated_moves.push(ChessMove::from_bitmove(buffer.get(idx), &board));
+    if board.halfmove_clock() >= 100 {
+        return Ok(Some(GameEnd::Draw("Fifty-move rule".to_string())));
+    }
+    if count_repetitions(fen, &mut board, history)? >= 3 {
+        return Ok(Some(GameEnd::Draw("Threefold repetition".to_string())));
     }
+    return Ok(None);
+}
+
+// only the fens with the same placement and side to move as the current one are parsed, the rest
+// are ruled out by comparing the strings, so a long history stays cheap on every move
+fn count_repetitions(fen: &str, board: &mut Board, history: &[String]) -> Result<usize, FenError> {
+    let key = board.repetition_key();
+    let mut count = 1;
+
+    for previous in history {
+        let same_placement_and_side = previous
+            .split_whitespace()
+            .take(2)
+            .eq(fen.split_whitespace().take(2));
+        if !same_placement_and_side {
+            continue;
+        }
+        if Board::try_from_fen(previous)?.repetition_key() == key {
+            count += 1;
+        }
+    }
+    return Ok(count);
+}
+
+pub fn get_board_after_move(fen: &str, chess_move: &ChessMove) -> Result<String, FenError> {
+    let start = Instant::now();
+    let mut board = Board::try_from_fen(fen)?;
+    let played_move = chess_move.to_bitmove();
 
-    println!(
-        "get_available_moves resulted in {} moves",
-        generated_moves.len()
+    board.make_move(&played_move);
+
+    debug!(
+        "get_board_after_move answered {} in {:?}",
+        played_move.uci_notation(),
+        start.elapsed()
+    );
+    return Ok(board.fen());
+}
+
+// like get_board_after_move, but only plays the move if it is legal in fen and names the right pieces
+pub fn try_play(fen: &str, chess_move: &ChessMove) -> Result<String, IllegalMove> {
+    let start = Instant::now();
+    let mut board = Board::try_from_fen(fen)?;
+    let played_move = chess_move.validate_on_board(&mut board)?;
+
+    board.make_move(&played_move);
+
+    debug!(
+        "try_play answered {} in {:?}",
+        played_move.uci_notation(),
+        start.elapsed()
     );
-    return generated_moves;
+    return Ok(board.fen());
 }
 
-pub fn is_game_over(fen: &str) -> Option<GameEnd> {
-    let mut board = Board::build(fen);
+// the uci_move is given in long algebraic notation (e.g. e2e4, e7e8q), None if it is not legal
+// castling can also be given as the king taking its own rook (e.g. e1h1), the way chess960 guis send it
+pub fn get_board_after_uci_move(fen: &str, uci_move: &str) -> Result<Option<String>, FenError> {
+    let mut board = Board::try_from_fen(fen)?;
     let mut buffer = MoveBuffer::new();
-    let mut temp_buffer = MoveBuffer::new();
-    let in_check = board.collect_moves(&mut buffer, &mut temp_buffer);
 
-    println!("is_game_over answered");
-    if buffer.count() > 0 {
-        return None;
+    board.generate_moves(&mut buffer, MoveFilter::All);
+
+    // in chess960 a king step can land on the square the king castles to, the step wins then and
+    // the castle has to be given as the king taking its rook
+    let moves = buffer.contents();
+    let played_move = match moves
+        .iter()
+        .find(|bitmove| {
+            bitmove.move_type() != BitMoveType::Castle && bitmove.uci_notation() == uci_move
+        })
+        .or_else(|| {
+            moves.iter().find(|bitmove| {
+                bitmove.move_type() == BitMoveType::Castle
+                    && (bitmove.uci_notation() == uci_move
+                        || board.castle_uci_notation(bitmove) == uci_move)
+            })
+        }) {
+        Some(bitmove) => *bitmove,
+        None => return Ok(None),
+    };
+    board.make_move(&played_move);
+
+    return Ok(Some(board.fen()));
+}
+
+// the Fischer Random starting position with the given number (0-959), 518 is the standard setup
+pub fn get_chess960_position(index: u16) -> Option<String> {
+    return Board::new_chess960(index).map(|board| board.fen());
+}
+
+pub fn get_position_hash(fen: &str) -> Result<u64, FenError> {
+    let board = Board::try_from_fen(fen)?;
+    return Ok(board.hash());
+}
+
+pub fn perft(fen: &str, depth: u32) -> Result<u64, FenError> {
+    let mut board = Board::try_from_fen(fen)?;
+    return Ok(board.perft(depth));
+}
+
+pub fn divide(fen: &str, depth: u32) -> Result<Vec<(ChessMove, u64)>, FenError> {
+    let mut board = Board::try_from_fen(fen)?;
+    let mut move_counts: Vec<(ChessMove, u64)> = vec![];
+
+    if depth == 0 {
+        return Ok(move_counts);
     }
-    if !in_check {
-        return Some(GameEnd::Draw("".to_string()));
+
+    for bitmove in board.legal_moves(MoveFilter::All) {
+        let chess_move = ChessMove::from_bitmove(&bitmove, &board);
+        board.make_move(&bitmove);
+        move_counts.push((chess_move, board.perft(depth - 1)));
+        board.unmake_move();
     }
-    return if board.side_to_move() == 0 {
-        Some(GameEnd::BlackWon("".to_string()))
-    } else {
-        Some(GameEnd::WhiteWon("".to_string()))
-    };
+    return Ok(move_counts);
 }
 
-pub fn get_board_after_move(fen: &str, chess_move: &ChessMove) -> String {
-    let mut board = Board::build(fen);
-    let played_move = chess_move.to_bitmove();
+// centipawns from the side to move's perspective
+pub fn evaluate(fen: &str) -> Result<i32, FenError> {
+    let board = Board::try_from_fen(fen)?;
+    return Ok(board.evaluate());
+}
 
-    println!("get_board_after_move answered");
-    board.make_move(&played_move);
+pub fn best_move(fen: &str, limits: SearchLimits) -> Result<SearchResult, FenError> {
+    let mut board = Board::try_from_fen(fen)?;
+    let mut searcher = Searcher::new(limits);
+    return Ok(searcher.search(&mut board));
+}
 
-    return board.fen();
+// same as best_move, but reports the result of every completed iteration as the search deepens,
+// history holds the fen of every position that occurred before the current one, so the search
+// sees repetitions of them
+pub fn best_move_with_progress(
+    fen: &str,
+    history: &[String],
+    limits: SearchLimits,
+    mut on_iteration: impl FnMut(&SearchResult),
+) -> Result<SearchResult, FenError> {
+    let mut board = Board::try_from_fen(fen)?;
+    let mut searcher = Searcher::new(limits);
+    let game_history = history
+        .iter()
+        .map(|fen| Board::try_from_fen(fen).map(|board| board.hash()))
+        .collect::<Result<Vec<u64>, FenError>>()?;
+    searcher.set_game_history(game_history);
+    return Ok(searcher.search_with_callback(&mut board, &mut on_iteration));
 }
 
 #[cfg(test)]
@@ -64,6 +257,7 @@ mod tests {
     use crate::boardsquare::BoardSquare;
     use crate::gameend::GameEnd;
     use crate::piecetype::PieceType::*;
+    use crate::sanerror::SanError;
 
     use super::*;
 
@@ -504,7 +698,7 @@ mod tests {
         ];
 
         for case in 0..2 {
-            let mut generated_moves = get_available_moves(boards[case]);
+            let mut generated_moves = get_available_moves(boards[case]).unwrap();
 
             generated_moves.sort();
             expected_moves[case].sort();
@@ -513,6 +707,299 @@ mod tests {
         }
     }
 
+    #[test]
+    fn en_passant_test() {
+        let boards: [&str; 5] = [
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "8/8/8/K2pP2r/8/8/8/7k w - d6 0 1",
+            "8/5b2/8/3pP3/2K5/8/8/7k w - d6 0 1",
+            "8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1",
+            "8/8/8/2k5/3Pp3/8/8/4K3 b - - 0 1",
+        ];
+        let mut expected_moves: Vec<Vec<ChessMove>> = vec![
+            vec![ChessMove::en_passant(
+                WhitePawn,
+                BoardSquare::from_coord(4, 4),
+                BoardSquare::from_coord(5, 5),
+                BlackPawn,
+                BoardSquare::from_coord(5, 4),
+            )],
+            vec![],
+            vec![],
+            vec![ChessMove::en_passant(
+                BlackPawn,
+                BoardSquare::from_coord(4, 3),
+                BoardSquare::from_coord(3, 2),
+                WhitePawn,
+                BoardSquare::from_coord(3, 3),
+            )],
+            vec![],
+        ];
+
+        for case in 0..5 {
+            let mut generated_moves: Vec<ChessMove> = get_available_moves(boards[case])
+                .unwrap()
+                .into_iter()
+                .filter(|chess_move| matches!(chess_move, ChessMove::EnPassant { .. }))
+                .collect();
+
+            generated_moves.sort();
+            expected_moves[case].sort();
+            assert_eq!(generated_moves, expected_moves[case]);
+        }
+    }
+
+    #[test]
+    fn get_board_after_move_test() {
+        let boards: [&str; 3] = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 1",
+            "8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1",
+        ];
+        let moves: [ChessMove; 3] = [
+            ChessMove::quiet(
+                WhitePawn,
+                BoardSquare::from_coord(4, 1),
+                BoardSquare::from_coord(4, 3),
+                None,
+            ),
+            ChessMove::en_passant(
+                WhitePawn,
+                BoardSquare::from_coord(4, 4),
+                BoardSquare::from_coord(5, 5),
+                BlackPawn,
+                BoardSquare::from_coord(5, 4),
+            ),
+            ChessMove::en_passant(
+                BlackPawn,
+                BoardSquare::from_coord(4, 3),
+                BoardSquare::from_coord(3, 2),
+                WhitePawn,
+                BoardSquare::from_coord(3, 3),
+            ),
+        ];
+        let expected_fens: [&str; 3] = [
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            "rnbqkbnr/ppp1p1pp/5P2/3p4/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+            "8/8/8/2k5/8/3p4/8/4K3 w - - 0 2",
+        ];
+
+        for case in 0..3 {
+            assert_eq!(
+                get_board_after_move(boards[case], &moves[case]).unwrap(),
+                expected_fens[case]
+            );
+        }
+    }
+
+    #[test]
+    fn try_play_test() {
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let e4 = ChessMove::quiet(
+            WhitePawn,
+            BoardSquare::from_coord(4, 1),
+            BoardSquare::from_coord(4, 3),
+            None,
+        );
+        assert_eq!(
+            try_play(fen, &e4).unwrap(),
+            get_board_after_move(fen, &e4).unwrap()
+        );
+
+        let rejected: [(&str, ChessMove, IllegalMove); 9] = [
+            (
+                fen,
+                ChessMove::quiet(
+                    WhitePawn,
+                    BoardSquare { x: 9, y: 7 },
+                    BoardSquare::from_coord(4, 3),
+                    None,
+                ),
+                IllegalMove::OffBoard(BoardSquare { x: 9, y: 7 }),
+            ),
+            (
+                fen,
+                ChessMove::quiet(
+                    WhitePawn,
+                    BoardSquare::from_coord(4, 1),
+                    BoardSquare { x: 4, y: 30 },
+                    None,
+                ),
+                IllegalMove::OffBoard(BoardSquare { x: 4, y: 30 }),
+            ),
+            (
+                fen,
+                ChessMove::quiet(
+                    WhitePawn,
+                    BoardSquare::from_coord(4, 2),
+                    BoardSquare::from_coord(4, 3),
+                    None,
+                ),
+                IllegalMove::EmptySquare("e3e4".to_string()),
+            ),
+            (
+                fen,
+                ChessMove::quiet(
+                    BlackPawn,
+                    BoardSquare::from_coord(4, 6),
+                    BoardSquare::from_coord(4, 4),
+                    None,
+                ),
+                IllegalMove::OpponentPiece("e7e5".to_string()),
+            ),
+            (
+                fen,
+                ChessMove::quiet(
+                    WhiteQueen,
+                    BoardSquare::from_coord(4, 1),
+                    BoardSquare::from_coord(4, 3),
+                    None,
+                ),
+                IllegalMove::WrongPieceType("e2e4".to_string()),
+            ),
+            (
+                fen,
+                ChessMove::quiet(
+                    WhitePawn,
+                    BoardSquare::from_coord(4, 1),
+                    BoardSquare::from_coord(4, 4),
+                    None,
+                ),
+                IllegalMove::NotLegal("e2e5".to_string()),
+            ),
+            (
+                "4k3/1P6/8/8/8/8/8/4K3 w - - 0 1",
+                ChessMove::quiet(
+                    WhitePawn,
+                    BoardSquare::from_coord(1, 6),
+                    BoardSquare::from_coord(1, 7),
+                    None,
+                ),
+                IllegalMove::WrongPromotion("b7b8".to_string()),
+            ),
+            (
+                "4k3/1P6/8/8/8/8/8/4K3 w - - 0 1",
+                ChessMove::quiet(
+                    WhitePawn,
+                    BoardSquare::from_coord(1, 6),
+                    BoardSquare::from_coord(1, 7),
+                    Some(BlackQueen),
+                ),
+                IllegalMove::WrongPromotion("b7b8q".to_string()),
+            ),
+            (
+                "4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1",
+                ChessMove::capture(
+                    WhitePawn,
+                    BoardSquare::from_coord(4, 3),
+                    BoardSquare::from_coord(3, 4),
+                    BlackKnight,
+                    None,
+                ),
+                IllegalMove::WrongCapturedPiece("e4d5".to_string()),
+            ),
+        ];
+        for (fen, chess_move, error) in rejected {
+            assert_eq!(try_play(fen, &chess_move), Err(error));
+        }
+
+        // pinned pieces and moves into check are not in the legal set
+        let pinned = ChessMove::quiet(
+            WhiteKnight,
+            BoardSquare::from_coord(4, 1),
+            BoardSquare::from_coord(2, 2),
+            None,
+        );
+        assert_eq!(
+            try_play("4r1k1/8/8/8/8/8/4N3/4K3 w - - 0 1", &pinned),
+            Err(IllegalMove::NotLegal("e2c3".to_string()))
+        );
+        assert!(matches!(
+            try_play("8/8/8/8/8/8/8/8 w - - 0 1", &e4),
+            Err(IllegalMove::InvalidFen(_))
+        ));
+    }
+
+    #[test]
+    fn move_counters_test() {
+        let boards: [&str; 4] = [
+            "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 12 40",
+            "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 12 40",
+            "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 12 40",
+            "r3k2r/8/8/8/8/8/1p6/R3K2R b KQkq - 12 40",
+        ];
+        let moves: [ChessMove; 4] = [
+            ChessMove::castle(
+                WhiteKing,
+                BoardSquare::from_coord(4, 0),
+                BoardSquare::from_coord(6, 0),
+                WhiteRook,
+                BoardSquare::from_coord(7, 0),
+                BoardSquare::from_coord(5, 0),
+            ),
+            ChessMove::quiet(
+                BlackRook,
+                BoardSquare::from_coord(0, 7),
+                BoardSquare::from_coord(0, 6),
+                None,
+            ),
+            ChessMove::capture(
+                BlackRook,
+                BoardSquare::from_coord(0, 7),
+                BoardSquare::from_coord(0, 0),
+                WhiteRook,
+                None,
+            ),
+            ChessMove::quiet(
+                BlackPawn,
+                BoardSquare::from_coord(1, 1),
+                BoardSquare::from_coord(1, 0),
+                Some(BlackQueen),
+            ),
+        ];
+        let expected_fens: [&str; 4] = [
+            "r3k2r/8/8/8/8/8/8/R4RK1 b kq - 13 40",
+            "4k2r/r7/8/8/8/8/8/R3K2R w KQk - 13 41",
+            "4k2r/8/8/8/8/8/8/r3K2R w Kk - 0 41",
+            "r3k2r/8/8/8/8/8/8/Rq2K2R w KQkq - 0 41",
+        ];
+
+        for case in 0..4 {
+            assert_eq!(
+                get_board_after_move(boards[case], &moves[case]).unwrap(),
+                expected_fens[case]
+            );
+        }
+    }
+
+    #[test]
+    fn move_counter_overflow_test() {
+        let fen = "4k3/8/8/8/8/8/8/4K2R b - - 65535 65535";
+        let king_move = ChessMove::quiet(
+            BlackKing,
+            BoardSquare::from_coord(4, 7),
+            BoardSquare::from_coord(5, 7),
+            None,
+        );
+        assert_eq!(
+            try_play(fen, &king_move).unwrap(),
+            "5k2/8/8/8/8/8/8/4K2R w - - 65535 65535"
+        );
+
+        let castle = ChessMove::castle(
+            WhiteKing,
+            BoardSquare::from_coord(4, 0),
+            BoardSquare::from_coord(6, 0),
+            WhiteRook,
+            BoardSquare::from_coord(7, 0),
+            BoardSquare::from_coord(5, 0),
+        );
+        assert_eq!(
+            try_play("4k3/8/8/8/8/8/8/4K2R w K - 65535 65535", &castle).unwrap(),
+            "4k3/8/8/8/8/8/8/5RK1 b - - 65535 65535"
+        );
+    }
+
     #[test]
     fn is_game_over_test() {
         let boards: [&str; 4] = [
@@ -523,16 +1010,355 @@ mod tests {
         ];
         let expected_results: [Option<GameEnd>; 4] = [
             None,
-            Some(GameEnd::WhiteWon("".to_string())),
-            Some(GameEnd::BlackWon("".to_string())),
-            Some(GameEnd::Draw("".to_string())),
+            Some(GameEnd::WhiteWon("Checkmate".to_string())),
+            Some(GameEnd::BlackWon("Checkmate".to_string())),
+            Some(GameEnd::Draw("Stalemate".to_string())),
         ];
 
         for case in 0..4 {
             let fen = boards[case];
-            let actual = is_game_over(fen);
+            let actual = is_game_over(fen).unwrap();
             assert_eq!(actual, expected_results[case]);
         }
     }
-}
+    #[test]
+    fn insufficient_material_test() {
+        let boards: [&str; 7] = [
+            "8/8/4k3/8/8/3K4/8/8 w - - 0 1",
+            "8/8/4k3/8/8/3K4/5N2/8 w - - 0 1",
+            "8/8/4k3/8/8/3K4/5B2/8 b - - 0 1",
+            "8/2b5/4k3/8/8/3K4/5B2/8 w - - 0 1",
+            "8/3b4/4k3/8/8/3K4/5B2/8 w - - 0 1",
+            "8/2n5/4k3/8/8/3K4/5N2/8 w - - 0 1",
+            "8/8/4k3/8/8/3K4/5P2/8 w - - 0 1",
+        ];
+        let expected_results: [Option<GameEnd>; 7] = [
+            Some(GameEnd::Draw("Insufficient material".to_string())),
+            Some(GameEnd::Draw("Insufficient material".to_string())),
+            Some(GameEnd::Draw("Insufficient material".to_string())),
+            Some(GameEnd::Draw("Insufficient material".to_string())),
+            None,
+            None,
+            None,
+        ];
+
+        for case in 0..7 {
+            assert_eq!(is_game_over(boards[case]).unwrap(), expected_results[case]);
+        }
+    }
+
+    #[test]
+    fn has_mating_material_test() {
+        let cases: [(&str, bool, bool); 10] = [
+            ("4k3/8/8/8/8/8/8/4K3 w - - 0 1", false, false),
+            ("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", true, false),
+            ("4k3/8/8/8/8/8/8/3NK3 b - - 0 1", false, false),
+            // the rook can block its own king in, so the knight can still mate
+            ("4kr2/8/8/8/8/8/8/3NK3 w - - 0 1", true, true),
+            ("4k3/4p3/8/8/8/8/8/2B1K3 w - - 0 1", true, true),
+            // bishops on opposite colours can help each other, on the same colour they cannot
+            ("2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1", true, true),
+            ("3bk3/8/8/8/8/8/8/2B1K3 w - - 0 1", false, false),
+            ("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1", true, false),
+            ("4k3/8/8/8/8/4B3/8/2B1K3 w - - 0 1", false, false),
+            ("4k3/8/8/8/8/8/8/1NN1K3 w - - 0 1", true, false),
+        ];
+
+        for (fen, white, black) in cases {
+            assert_eq!(has_mating_material(fen, true).unwrap(), white, "{}", fen);
+            assert_eq!(has_mating_material(fen, false).unwrap(), black, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn move_rule_draw_test() {
+        let boards: [&str; 4] = [
+            "4k3/8/8/8/8/8/4P3/4K2R w - - 99 80",
+            "4k3/8/8/8/8/8/4P3/4K2R w - - 100 80",
+            "4k3/8/8/8/8/8/4P3/4K2R w - - 150 100",
+            "7k/5Q2/6K1/8/8/8/8/8 b - - 150 100",
+        ];
+        let expected_game_ends: [Option<GameEnd>; 4] = [
+            None,
+            None,
+            Some(GameEnd::Draw("Seventy-five-move rule".to_string())),
+            Some(GameEnd::Draw("Stalemate".to_string())),
+        ];
+        let expected_claims: [Option<GameEnd>; 4] = [
+            None,
+            Some(GameEnd::Draw("Fifty-move rule".to_string())),
+            Some(GameEnd::Draw("Fifty-move rule".to_string())),
+            Some(GameEnd::Draw("Fifty-move rule".to_string())),
+        ];
+
+        for case in 0..4 {
+            assert_eq!(is_game_over(boards[case]).unwrap(), expected_game_ends[case]);
+            assert_eq!(
+                get_claimable_draw(boards[case], &[]).unwrap(),
+                expected_claims[case]
+            );
+        }
+    }
+
+    #[test]
+    fn repetition_draw_test() {
+        let cycle: [&str; 4] = [
+            "4k3/8/8/8/8/8/4P3/R3K3 w Q - 0 1",
+            "4k3/8/8/8/8/8/4P3/R2K4 b - - 1 1",
+            "3k4/8/8/8/8/8/4P3/R2K4 w - - 2 2",
+            "3k4/8/8/8/8/8/4P3/R3K3 b - - 3 2",
+        ];
+        let back_to_start = "4k3/8/8/8/8/8/4P3/R3K3 w - - 4 3";
+
+        // the first position had castling rights, so it does not count as a repetition
+        let mut history: Vec<String> = cycle.iter().map(|fen| fen.to_string()).collect();
+        assert_eq!(get_claimable_draw(back_to_start, &history).unwrap(), None);
+
+        for occurrence in 2..=5 {
+            history.push(back_to_start.to_string());
+            for position in &cycle[1..] {
+                history.push(position.to_string());
+            }
+
+            let claim = get_claimable_draw(back_to_start, &history).unwrap();
+            let game_end = is_game_over_with_history(back_to_start, &history).unwrap();
+            if occurrence >= 3 {
+                assert_eq!(claim, Some(GameEnd::Draw("Threefold repetition".to_string())));
+            } else {
+                assert_eq!(claim, None);
+            }
+            if occurrence >= 5 {
+                assert_eq!(game_end, Some(GameEnd::Draw("Fivefold repetition".to_string())));
+            } else {
+                assert_eq!(game_end, None);
+            }
+        }
+    }
+
+    #[test]
+    fn divide_test() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let move_counts = divide(fen, 3).unwrap();
+
+        assert_eq!(move_counts.len(), 48);
+        assert_eq!(
+            move_counts.iter().map(|(_, nodes)| nodes).sum::<u64>(),
+            perft(fen, 3).unwrap()
+        );
+
+        let expected_counts: [(&str, u64); 4] = [
+            ("e1g1", 2059),
+            ("e1c1", 1887),
+            ("d5e6", 2241),
+            ("e5f7", 2080),
+        ];
+        for (notation, expected) in expected_counts {
+            let (_, nodes) = move_counts
+                .iter()
+                .find(|(chess_move, _)| chess_move.notation() == notation)
+                .unwrap();
+            assert_eq!(*nodes, expected);
+        }
+    }
+
+    #[test]
+    fn invalid_fen_test() {
+        let boards: [&str; 4] = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR",
+            "8/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K2R w KQ - 0 1",
+        ];
+        let expected_errors: [FenError; 4] = [
+            FenError::InvalidSideToMove("x".to_string()),
+            FenError::MissingField("side to move".to_string()),
+            FenError::MissingKing("black".to_string()),
+            FenError::InvalidCastling("KQ".to_string()),
+        ];
+
+        for case in 0..4 {
+            assert_eq!(get_available_moves(boards[case]).unwrap_err(), expected_errors[case]);
+            assert_eq!(is_game_over(boards[case]).unwrap_err(), expected_errors[case]);
+        }
+    }
+
+    #[test]
+    fn get_board_after_uci_move_test() {
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(
+            get_board_after_uci_move(fen, "e2e4").unwrap(),
+            Some("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1".to_string())
+        );
+        assert_eq!(get_board_after_uci_move(fen, "e2e5").unwrap(), None);
+
+        let fen = "4k3/1P6/8/8/8/8/8/4K3 w - - 0 1";
+        assert_eq!(
+            get_board_after_uci_move(fen, "b7b8n").unwrap(),
+            Some("1N2k3/8/8/8/8/8/8/4K3 b - - 0 1".to_string())
+        );
+    }
+
+    #[test]
+    fn chess960_test() {
+        let fen = get_chess960_position(518).unwrap();
+        assert_eq!(fen, "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(get_chess960_position(960), None);
+
+        // shredder-fen rights, the king already stands on g1 for the short castle
+        let fen = "1r4kr/pppppppp/8/8/8/8/PPPPPPPP/1R4KR w HBhb - 0 1";
+        let castles: Vec<ChessMove> = get_available_moves(fen)
+            .unwrap()
+            .into_iter()
+            .filter(|chess_move| matches!(chess_move, ChessMove::Castle { .. }))
+            .collect();
+        assert_eq!(castles.len(), 2);
 
+        let fen = "1r4kr/pppppppp/8/8/8/8/PPPPPPPP/1R4KR w KQkq - 0 1";
+        let short_castle = ChessMove::from_san("O-O", fen).unwrap();
+        assert_eq!(
+            short_castle,
+            ChessMove::castle(
+                WhiteKing,
+                BoardSquare::from_index(6),
+                BoardSquare::from_index(6),
+                WhiteRook,
+                BoardSquare::from_index(7),
+                BoardSquare::from_index(5)
+            )
+        );
+        assert_eq!(
+            get_board_after_move(fen, &short_castle).unwrap(),
+            "1r4kr/pppppppp/8/8/8/8/PPPPPPPP/1R3RK1 b kq - 1 1"
+        );
+        assert_eq!(
+            get_board_after_uci_move(fen, "g1b1").unwrap(),
+            Some("1r4kr/pppppppp/8/8/8/8/PPPPPPPP/2KR3R b kq - 1 1".to_string())
+        );
+        assert_eq!(ChessMove::from_san("O-O-O", fen).unwrap().to_san(fen).unwrap(), "O-O-O");
+
+        // f1g1 is a king step here, castling is only f1h1
+        let fen = "4k3/8/8/8/8/8/8/5K1R w H - 0 1";
+        assert_eq!(
+            get_board_after_uci_move(fen, "f1g1").unwrap(),
+            Some("4k3/8/8/8/8/8/8/6KR b - - 1 1".to_string())
+        );
+        assert_eq!(
+            get_board_after_uci_move(fen, "f1h1").unwrap(),
+            Some("4k3/8/8/8/8/8/8/5RK1 b - - 1 1".to_string())
+        );
+
+        // the b1 rook shields c1 from the a1 rook until it moves away
+        let fen = "4k3/8/8/8/8/8/8/rR1K4 w Q - 0 1";
+        assert!(ChessMove::from_san("O-O-O", fen).is_err());
+    }
+
+    #[test]
+    fn san_test() {
+        let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+        let moves = get_available_moves(fen).unwrap();
+        let mut san_moves: Vec<String> = moves
+            .iter()
+            .map(|chess_move| chess_move.to_san(fen).unwrap())
+            .collect();
+        san_moves.sort();
+
+        assert_eq!(san_moves.len(), 27);
+        assert!(san_moves.contains(&"Nxe5".to_string()));
+        assert!(san_moves.contains(&"Bb5".to_string()));
+        assert!(san_moves.contains(&"Ng1".to_string()));
+
+        // every generated san resolves back to the same move
+        for chess_move in moves.iter() {
+            let san = chess_move.to_san(fen).unwrap();
+            assert_eq!(ChessMove::from_san(&san, fen).unwrap(), *chess_move);
+        }
+
+        // to_san only names legal moves
+        let start_fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let empty_square = ChessMove::quiet(
+            WhitePawn,
+            BoardSquare::from_coord(4, 3),
+            BoardSquare::from_coord(4, 4),
+            None,
+        );
+        assert_eq!(
+            empty_square.to_san(start_fen).unwrap_err(),
+            IllegalMove::EmptySquare("e4e5".to_string())
+        );
+        let not_on_move = ChessMove::quiet(
+            BlackPawn,
+            BoardSquare::from_coord(4, 6),
+            BoardSquare::from_coord(4, 4),
+            None,
+        );
+        assert_eq!(
+            not_on_move.to_san(start_fen).unwrap_err(),
+            IllegalMove::OpponentPiece("e7e5".to_string())
+        );
+
+        assert_eq!(
+            ChessMove::from_san("Ke3", fen).unwrap_err(),
+            SanError::IllegalMove("Ke3".to_string())
+        );
+        assert_eq!(
+            ChessMove::from_san("e4", "8/8/8/8/8/8/8/4K3 w - - 0 1").unwrap_err(),
+            SanError::InvalidFen(FenError::MissingKing("black".to_string()))
+        );
+    }
+
+    #[test]
+    fn evaluate_test() {
+        assert_eq!(
+            evaluate("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap(),
+            0
+        );
+
+        // white is a rook up
+        let white_to_move = evaluate("4k3/pppp4/8/8/8/8/PPPP4/R3K3 w - - 0 1").unwrap();
+        let black_to_move = evaluate("4k3/pppp4/8/8/8/8/PPPP4/R3K3 b - - 0 1").unwrap();
+        assert!(white_to_move > 300);
+        assert_eq!(black_to_move, -white_to_move);
+
+        assert!(evaluate("4k3/8/8/8/8/8/8/4K3 x - - 0 1").is_err());
+    }
+
+    #[test]
+    fn best_move_test() {
+        // scholar's mate
+        let fen = "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4";
+        let result = best_move(fen, SearchLimits::depth(2)).unwrap();
+
+        assert_eq!(result.best_move.as_ref().unwrap().notation(), "h5f7");
+        assert_eq!(result.mate_in(), Some(1));
+        assert_eq!(result.principal_variation.len(), 1);
+        assert!(result.nodes > 0);
+
+        assert_eq!(
+            best_move("8/8/8/8/8/8/8/4K3 w - - 0 1", SearchLimits::depth(2)).unwrap_err(),
+            FenError::MissingKing("black".to_string())
+        );
+    }
+
+    #[test]
+    fn best_move_with_history_test() {
+        // a queen down, but Kg1 repeats the first position of the history
+        let history: Vec<String> = [
+            "6k1/8/q7/8/8/8/8/6K1 b - - 10 40",
+            "7k/8/q7/8/8/8/8/6K1 w - - 11 41",
+            "7k/8/q7/8/8/8/8/7K b - - 12 41",
+        ]
+        .iter()
+        .map(|fen| fen.to_string())
+        .collect();
+        let fen = "6k1/8/q7/8/8/8/8/7K w - - 13 42";
+
+        let without_history =
+            best_move_with_progress(fen, &[], SearchLimits::depth(3), |_| {}).unwrap();
+        assert!(without_history.score < -500);
+
+        let with_history =
+            best_move_with_progress(fen, &history, SearchLimits::depth(3), |_| {}).unwrap();
+        assert_eq!(with_history.score, 0);
+        assert_eq!(with_history.best_move.unwrap().notation(), "h1g1");
+    }
+}
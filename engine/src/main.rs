@@ -1,5 +1,422 @@
-mod bitboard;
+use std::io::{self, BufRead};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use engine::boardsquare::BoardSquare;
+use engine::chessmove::ChessMove;
+use engine::search::{MAX_DEPTH, SearchLimits, SearchResult};
+
+const ENGINE_NAME: &str = "Knightly";
+const ENGINE_AUTHOR: &str = "Knightly developers";
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+const DEFAULT_MOVE_OVERHEAD: u64 = 30;
+const MAX_MOVE_OVERHEAD: u64 = 5000;
+// the number of moves the remaining clock time is split between when the gui does not send movestogo
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+struct Uci {
+    fen: String,
+    // the positions the game went through before fen, for repetition detection
+    history: Vec<String>,
+    move_overhead: Duration,
+    chess960: bool,
+    stop: Arc<AtomicBool>,
+    search_thread: Option<JoinHandle<()>>,
+}
+
+// what the gui asked for with the go command
+#[derive(Debug, Default, PartialEq)]
+struct GoCommand {
+    depth: Option<u8>,
+    nodes: Option<u64>,
+    movetime: Option<Duration>,
+    wtime: Option<Duration>,
+    btime: Option<Duration>,
+    winc: Option<Duration>,
+    binc: Option<Duration>,
+    movestogo: Option<u32>,
+    infinite: bool,
+}
 
 fn main() {
-    println!("Hello, world!");
+    let mut uci = Uci::new();
+    let stdin = io::stdin();
+
+    for line in stdin.lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        if !uci.handle_command(&line) {
+            break;
+        }
+    }
+    uci.stop_search();
+}
+
+impl Uci {
+    fn new() -> Self {
+        return Self {
+            fen: START_FEN.to_string(),
+            history: Vec::new(),
+            move_overhead: Duration::from_millis(DEFAULT_MOVE_OVERHEAD),
+            chess960: false,
+            stop: Arc::new(AtomicBool::new(false)),
+            search_thread: None,
+        };
+    }
+
+    // returns false once the engine should quit
+    fn handle_command(&mut self, line: &str) -> bool {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command, args)) = tokens.split_first() else {
+            return true;
+        };
+
+        match command {
+            "uci" => {
+                println!("id name {} {}", ENGINE_NAME, env!("CARGO_PKG_VERSION"));
+                println!("id author {}", ENGINE_AUTHOR);
+                println!(
+                    "option name Move Overhead type spin default {} min 0 max {}",
+                    DEFAULT_MOVE_OVERHEAD, MAX_MOVE_OVERHEAD
+                );
+                println!("option name UCI_Chess960 type check default false");
+                println!("uciok");
+            }
+            "isready" => println!("readyok"),
+            "ucinewgame" => {
+                self.stop_search();
+                self.fen = START_FEN.to_string();
+                self.history.clear();
+            }
+            "position" => {
+                self.stop_search();
+                match parse_position(args) {
+                    Ok((fen, history)) => {
+                        self.fen = fen;
+                        self.history = history;
+                    }
+                    Err(message) => println!("info string {}", message),
+                }
+            }
+            "go" => {
+                self.stop_search();
+                self.start_search(parse_go(args));
+            }
+            "stop" => self.stop_search(),
+            "setoption" => self.set_option(args),
+            "quit" => return false,
+            _ => println!("info string unknown command {}", command),
+        }
+        return true;
+    }
+
+    fn set_option(&mut self, args: &[&str]) {
+        // setoption name <id> [value <x>], the name itself can contain spaces
+        let value_idx = args.iter().position(|&token| token == "value");
+        let name_end = value_idx.unwrap_or(args.len());
+        if name_end < 1 {
+            println!("info string setoption needs a name");
+            return;
+        }
+        let name = args[1..name_end].join(" ");
+        let value = value_idx.map(|idx| args[idx + 1..].join(" "));
+
+        match (name.to_lowercase().as_str(), value) {
+            ("move overhead", Some(value)) => match value.parse::<u64>() {
+                Ok(millis) => {
+                    self.move_overhead = Duration::from_millis(millis.min(MAX_MOVE_OVERHEAD))
+                }
+                Err(_) => println!("info string invalid value for Move Overhead: {}", value),
+            },
+            ("uci_chess960", Some(value)) => match value.as_str() {
+                "true" => self.chess960 = true,
+                "false" => self.chess960 = false,
+                _ => println!("info string invalid value for UCI_Chess960: {}", value),
+            },
+            _ => println!("info string unknown option {}", name),
+        }
+    }
+
+    fn start_search(&mut self, go: GoCommand) {
+        let side_to_move = self.fen.split_whitespace().nth(1).unwrap_or("w");
+        let mut limits = go.to_limits(side_to_move == "w", self.move_overhead);
+
+        self.stop = Arc::new(AtomicBool::new(false));
+        limits.stop = Some(self.stop.clone());
+        let stop = self.stop.clone();
+        let fen = self.fen.clone();
+        let history = self.history.clone();
+        let infinite = go.infinite;
+        let chess960 = self.chess960;
+
+        self.search_thread = Some(thread::spawn(move || {
+            let result = engine::best_move_with_progress(&fen, &history, limits, |result| {
+                print_info(result, chess960)
+            });
+
+            // in infinite mode the best move may only be sent after the gui says stop
+            if infinite {
+                while !stop.load(Ordering::Relaxed) {
+                    thread::sleep(Duration::from_millis(5));
+                }
+            }
+
+            match result {
+                Ok(result) => match result.best_move {
+                    Some(best_move) => {
+                        println!("bestmove {}", get_uci_notation(&best_move, chess960))
+                    }
+                    None => println!("bestmove 0000"),
+                },
+                Err(e) => {
+                    println!("info string {}", e);
+                    println!("bestmove 0000");
+                }
+            }
+        }));
+    }
+
+    fn stop_search(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(search_thread) = self.search_thread.take() {
+            let _ = search_thread.join();
+        }
+    }
+}
+
+impl GoCommand {
+    fn to_limits(&self, is_white_to_move: bool, move_overhead: Duration) -> SearchLimits {
+        let mut limits = SearchLimits {
+            depth: self.depth.map(|depth| depth.clamp(1, MAX_DEPTH)),
+            nodes: self.nodes,
+            movetime: self
+                .movetime
+                .map(|movetime| movetime.saturating_sub(move_overhead).max(Duration::from_millis(1))),
+            stop: None,
+        };
+        if self.infinite {
+            return limits;
+        }
+
+        let (time, increment) = if is_white_to_move {
+            (self.wtime, self.winc)
+        } else {
+            (self.btime, self.binc)
+        };
+        if let Some(time) = time {
+            let budget = get_time_budget(
+                time,
+                increment.unwrap_or(Duration::ZERO),
+                self.movestogo,
+                move_overhead,
+            );
+            limits.movetime = Some(limits.movetime.map_or(budget, |movetime| movetime.min(budget)));
+        }
+        return limits;
+    }
+}
+
+// an even share of the remaining time plus most of the increment, never more than what is on the clock
+fn get_time_budget(
+    time: Duration,
+    increment: Duration,
+    moves_to_go: Option<u32>,
+    move_overhead: Duration,
+) -> Duration {
+    let moves_to_go = moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+    let available = time.saturating_sub(move_overhead);
+    let budget = time / moves_to_go + increment * 3 / 4;
+
+    return budget.min(available).max(Duration::from_millis(1));
+}
+
+// position [startpos | fen <fen>] [moves <move1> ... <moveN>]
+// returns the final position and the ones before it
+fn parse_position(args: &[&str]) -> Result<(String, Vec<String>), String> {
+    let moves_idx = args
+        .iter()
+        .position(|&token| token == "moves")
+        .unwrap_or(args.len());
+
+    let mut fen = match args.first() {
+        Some(&"startpos") => START_FEN.to_string(),
+        Some(&"fen") => args[1..moves_idx].join(" "),
+        _ => return Err("position needs startpos or fen".to_string()),
+    };
+    engine::get_position_hash(&fen).map_err(|e| format!("invalid fen: {}", e))?;
+
+    let mut history = Vec::new();
+    for uci_move in args.iter().skip(moves_idx + 1) {
+        let next_fen = match engine::get_board_after_uci_move(&fen, uci_move) {
+            Ok(Some(next_fen)) => next_fen,
+            Ok(None) => return Err(format!("illegal move {}", uci_move)),
+            Err(e) => return Err(format!("invalid fen: {}", e)),
+        };
+        history.push(std::mem::replace(&mut fen, next_fen));
+    }
+    return Ok((fen, history));
+}
+
+fn parse_go(args: &[&str]) -> GoCommand {
+    let mut go = GoCommand::default();
+    let mut tokens = args.iter();
+
+    while let Some(&token) = tokens.next() {
+        let mut next_number = || tokens.next().and_then(|value| value.parse::<u64>().ok());
+        match token {
+            "depth" => go.depth = next_number().map(|depth| depth.min(MAX_DEPTH as u64) as u8),
+            "nodes" => go.nodes = next_number(),
+            "movetime" => go.movetime = next_number().map(Duration::from_millis),
+            "wtime" => go.wtime = next_number().map(Duration::from_millis),
+            "btime" => go.btime = next_number().map(Duration::from_millis),
+            "winc" => go.winc = next_number().map(Duration::from_millis),
+            "binc" => go.binc = next_number().map(Duration::from_millis),
+            "movestogo" => go.movestogo = next_number().map(|moves| moves as u32),
+            "infinite" => go.infinite = true,
+            _ => {}
+        }
+    }
+    return go;
+}
+
+// chess960 guis expect castling as the king taking its own rook
+fn get_uci_notation(chess_move: &ChessMove, chess960: bool) -> String {
+    return match chess_move {
+        ChessMove::Castle {
+            king_from,
+            rook_from,
+            ..
+        } if chess960 => {
+            let square = |sq: &BoardSquare| format!("{}{}", (b'a' + sq.x as u8) as char, sq.y + 1);
+            format!("{}{}", square(king_from), square(rook_from))
+        }
+        _ => chess_move.notation(),
+    };
+}
+
+fn print_info(result: &SearchResult, chess960: bool) {
+    let score = match result.mate_in() {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", result.score),
+    };
+    let pv: Vec<String> = result
+        .principal_variation
+        .iter()
+        .map(|chess_move| get_uci_notation(chess_move, chess960))
+        .collect();
+
+    println!(
+        "info depth {} seldepth {} score {} nodes {} nps {} time {} pv {}",
+        result.depth,
+        result.seldepth,
+        score,
+        result.nodes,
+        result.nodes_per_second(),
+        result.elapsed.as_millis(),
+        pv.join(" ")
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_position_test() {
+        assert_eq!(parse_position(&["startpos"]).unwrap(), (START_FEN.to_string(), vec![]));
+
+        let (fen, history) = parse_position(&["startpos", "moves", "e2e4", "e7e5", "g1f3"]).unwrap();
+        assert_eq!(fen, "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2");
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0], START_FEN);
+        assert_eq!(history[2], "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2");
+
+        assert_eq!(
+            parse_position(&["fen", "4k3/8/8/8/8/8/8/R3K3", "w", "Q", "-", "0", "1", "moves", "e1c1"]).unwrap().0,
+            "4k3/8/8/8/8/8/8/2KR4 b - - 1 1"
+        );
+
+        assert!(parse_position(&["startpos", "moves", "e2e5"]).is_err());
+        assert!(parse_position(&["fen", "8/8/8/8/8/8/8/8", "w", "-", "-", "0", "1"]).is_err());
+        assert!(parse_position(&[]).is_err());
+    }
+
+    #[test]
+    fn set_option_test() {
+        let mut uci = Uci::new();
+
+        // a missing name is reported, not a panic
+        uci.set_option(&["value", "5"]);
+        uci.set_option(&[]);
+
+        uci.set_option(&["name", "Move", "Overhead", "value", "100"]);
+        assert_eq!(uci.move_overhead, Duration::from_millis(100));
+        uci.set_option(&["name", "UCI_Chess960", "value", "true"]);
+        assert!(uci.chess960);
+    }
+
+    #[test]
+    fn parse_go_test() {
+        let go = parse_go(&["wtime", "60000", "btime", "30000", "winc", "1000", "binc", "500", "movestogo", "20"]);
+        assert_eq!(
+            go,
+            GoCommand {
+                wtime: Some(Duration::from_millis(60000)),
+                btime: Some(Duration::from_millis(30000)),
+                winc: Some(Duration::from_millis(1000)),
+                binc: Some(Duration::from_millis(500)),
+                movestogo: Some(20),
+                ..Default::default()
+            }
+        );
+
+        let go = parse_go(&["depth", "6", "nodes", "10000", "infinite"]);
+        assert_eq!(go.depth, Some(6));
+        assert_eq!(go.nodes, Some(10000));
+        assert!(go.infinite);
+
+        // unknown tokens and missing values are skipped
+        let go = parse_go(&["ponder", "movetime"]);
+        assert_eq!(go, GoCommand::default());
+    }
+
+    #[test]
+    fn chess960_notation_test() {
+        let fen = "1r4kr/pppppppp/8/8/8/8/PPPPPPPP/1R4KR w KQkq - 0 1";
+        let short_castle = ChessMove::from_san("O-O", fen).unwrap();
+        let long_castle = ChessMove::from_san("O-O-O", fen).unwrap();
+
+        assert_eq!(get_uci_notation(&short_castle, true), "g1h1");
+        assert_eq!(get_uci_notation(&long_castle, true), "g1b1");
+        assert_eq!(get_uci_notation(&long_castle, false), "g1c1");
+        assert_eq!(
+            parse_position(&["fen", "1r4kr/pppppppp/8/8/8/8/PPPPPPPP/1R4KR", "w", "HBhb", "-", "0", "1", "moves", "g1h1"]).unwrap().0,
+            "1r4kr/pppppppp/8/8/8/8/PPPPPPPP/1R3RK1 b kq - 1 1"
+        );
+    }
+
+    #[test]
+    fn time_management_test() {
+        let overhead = Duration::from_millis(DEFAULT_MOVE_OVERHEAD);
+        let go = parse_go(&["wtime", "60000", "btime", "30000", "winc", "1000", "binc", "0", "movestogo", "20"]);
+
+        assert_eq!(go.to_limits(true, overhead).movetime, Some(Duration::from_millis(3750)));
+        assert_eq!(go.to_limits(false, overhead).movetime, Some(Duration::from_millis(1500)));
+
+        // never more than what is left on the clock
+        let budget = get_time_budget(Duration::from_millis(100), Duration::from_millis(2000), None, overhead);
+        assert_eq!(budget, Duration::from_millis(70));
+
+        let go = parse_go(&["movetime", "1000"]);
+        assert_eq!(go.to_limits(true, overhead).movetime, Some(Duration::from_millis(970)));
+
+        let go = parse_go(&["infinite"]);
+        let limits = go.to_limits(true, overhead);
+        assert!(limits.depth.is_none() && limits.nodes.is_none() && limits.movetime.is_none());
+    }
 }
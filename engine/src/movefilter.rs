@@ -0,0 +1,14 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+// which of the legal moves a move generation pass produces
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveFilter {
+    // captures (en passant and capturing promotions included) first, then the quiet moves
+    All,
+    Captures,
+    // quiet moves, quiet promotions and castling
+    Quiets,
+    // every move that gives check, captures first
+    Checks,
+}
@@ -0,0 +1,688 @@
+use serde::{Deserialize, Serialize};
+
+use crate::bitboard::board::Board;
+use crate::movefilter::MoveFilter;
+use crate::chessmove::ChessMove;
+use crate::gameend::GameEnd;
+use crate::pgnerror::PgnError;
+use crate::sanerror::SanError;
+
+pub const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+const MAX_LINE_LENGTH: usize = 79;
+// the traditional suffix annotations and the NAGs they stand for
+const SUFFIX_ANNOTATIONS: [(&str, u8); 6] =
+    [("!", 1), ("?", 2), ("!!", 3), ("??", 4), ("!?", 5), ("?!", 6)];
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PgnGame {
+    // in the order they appear in the file
+    pub tags: Vec<(String, String)>,
+    pub starting_fen: String,
+    pub moves: Vec<PgnMove>,
+    pub result: Option<GameEnd>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PgnMove {
+    pub chess_move: ChessMove,
+    pub san: String,
+    // the position after the move
+    pub fen: String,
+    pub comment_before: Option<String>,
+    pub comment: Option<String>,
+    pub nags: Vec<u8>,
+    // alternatives to this move, each one starting from the position before it
+    pub variations: Vec<Vec<PgnMove>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    TagPair(String, String),
+    Comment(String),
+    Nag(u8),
+    VariationStart,
+    VariationEnd,
+    GameResult(String),
+    San(String),
+}
+
+impl PgnGame {
+    pub fn new(starting_fen: &str) -> Result<Self, PgnError> {
+        let board = Board::try_from_fen(starting_fen)?;
+        let mut game = Self {
+            tags: SEVEN_TAG_ROSTER
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            starting_fen: board.fen(),
+            moves: vec![],
+            result: None,
+        };
+        if game.starting_fen != START_FEN {
+            game.set_tag("SetUp", "1");
+            game.set_tag("FEN", &game.starting_fen.clone());
+        }
+        return Ok(game);
+    }
+
+    // the main line of a game played from starting_fen, e.g. for archiving a finished match
+    pub fn from_moves(starting_fen: &str, moves: &[ChessMove]) -> Result<Self, PgnError> {
+        let mut game = Self::new(starting_fen)?;
+        let mut board = Board::try_from_fen(starting_fen)?;
+
+        for chess_move in moves {
+            let bitmove = chess_move.to_bitmove();
+            if !board
+                .legal_moves(MoveFilter::All)
+                .any(|legal_move| legal_move == bitmove)
+            {
+                return Err(PgnError::InvalidMove(SanError::IllegalMove(chess_move.notation())));
+            }
+
+            let san = board.san(&bitmove);
+            let chess_move = ChessMove::from_bitmove(&bitmove, &board);
+            board.make_move(&bitmove);
+            game.moves.push(PgnMove::new(chess_move, san, board.fen()));
+        }
+        return Ok(game);
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        return self
+            .tags
+            .iter()
+            .find(|(tag_name, _)| tag_name == name)
+            .map(|(_, value)| value.as_str());
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag_name, _)| tag_name == name) {
+            Some(tag) => tag.1 = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn set_result(&mut self, result: Option<GameEnd>) {
+        self.set_tag("Result", result_token(&result));
+        if let Some(reason) = result.as_ref().map(game_end_reason).filter(|reason| !reason.is_empty()) {
+            self.set_tag("Termination", reason);
+        }
+        self.result = result;
+    }
+
+    // the starting position followed by the position after every main line move
+    pub fn positions(&self) -> Vec<String> {
+        let mut positions = vec![self.starting_fen.clone()];
+        positions.extend(self.moves.iter().map(|pgn_move| pgn_move.fen.clone()));
+        return positions;
+    }
+
+    pub fn main_line(&self) -> Vec<ChessMove> {
+        return self
+            .moves
+            .iter()
+            .map(|pgn_move| pgn_move.chess_move.clone())
+            .collect();
+    }
+
+    pub fn to_pgn(&self) -> String {
+        let mut pgn = String::new();
+        for (name, value) in self.tags.iter() {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, value));
+        }
+        pgn.push('\n');
+
+        let mut tokens: Vec<String> = vec![];
+        write_line(&mut tokens, &self.starting_fen, &self.moves);
+        tokens.push(result_token(&self.result).to_string());
+
+        // movetext lines are wrapped so none is longer than MAX_LINE_LENGTH characters
+        let mut line = String::new();
+        let mut previous_token = String::new();
+        for token in tokens {
+            // no space on the inner side of the parentheses around a variation
+            let separator = if line.is_empty() || previous_token == "(" || token == ")" { "" } else { " " };
+            if !line.is_empty() && line.len() + separator.len() + token.len() > MAX_LINE_LENGTH {
+                pgn.push_str(&line);
+                pgn.push('\n');
+                line.clear();
+            } else {
+                line.push_str(separator);
+            }
+            line.push_str(&token);
+            previous_token = token;
+        }
+        pgn.push_str(&line);
+        pgn.push('\n');
+
+        return pgn;
+    }
+}
+
+impl PgnMove {
+    fn new(chess_move: ChessMove, san: String, fen: String) -> Self {
+        return Self {
+            chess_move,
+            san,
+            fen,
+            comment_before: None,
+            comment: None,
+            nags: vec![],
+            variations: vec![],
+        };
+    }
+}
+
+// a pgn file can hold any number of games, one after another
+pub fn parse_pgn(pgn: &str) -> Result<Vec<PgnGame>, PgnError> {
+    let tokens = tokenize(pgn)?;
+    let mut games: Vec<PgnGame> = vec![];
+    let mut game_start = 0;
+
+    while game_start < tokens.len() {
+        // a game ends with its result, or when the tags of the next one begin
+        let mut game_end = game_start;
+        let mut has_movetext = false;
+        while game_end < tokens.len() {
+            match &tokens[game_end] {
+                Token::GameResult(_) => {
+                    game_end += 1;
+                    break;
+                }
+                Token::TagPair(_, _) if has_movetext => break,
+                Token::TagPair(_, _) => {}
+                _ => has_movetext = true,
+            }
+            game_end += 1;
+        }
+
+        games.push(parse_game(&tokens[game_start..game_end])?);
+        game_start = game_end;
+    }
+    return Ok(games);
+}
+
+fn parse_game(tokens: &[Token]) -> Result<PgnGame, PgnError> {
+    let mut tags: Vec<(String, String)> = vec![];
+    let mut idx = 0;
+    while let Some(Token::TagPair(name, value)) = tokens.get(idx) {
+        tags.push((name.clone(), value.clone()));
+        idx += 1;
+    }
+
+    let starting_fen = tags
+        .iter()
+        .find(|(name, _)| name == "FEN")
+        .map_or(START_FEN.to_string(), |(_, fen)| fen.clone());
+    let mut board = Board::try_from_fen(&starting_fen)?;
+
+    let mut result_text: Option<String> = None;
+    let movetext = match tokens.last() {
+        Some(Token::GameResult(result)) if tokens.len() > idx => {
+            result_text = Some(result.clone());
+            &tokens[idx..tokens.len() - 1]
+        }
+        _ => &tokens[idx..],
+    };
+
+    let moves = parse_line(movetext, &mut 0, &mut board, false)?;
+
+    let mut game = PgnGame {
+        tags,
+        starting_fen: board.fen(),
+        moves,
+        result: None,
+    };
+
+    // the movetext result wins over the tag, they should be the same anyway
+    let result_text = result_text.or(game.tag("Result").map(|result| result.to_string()));
+    let reason = game.tag("Termination").unwrap_or("").to_string();
+    game.result = match result_text.as_deref() {
+        Some("1-0") => Some(GameEnd::WhiteWon(reason)),
+        Some("0-1") => Some(GameEnd::BlackWon(reason)),
+        Some("1/2-1/2") => Some(GameEnd::Draw(reason)),
+        _ => None,
+    };
+    return Ok(game);
+}
+
+// parses moves until the end of the tokens, or the end of the variation when is_variation is set,
+// the board is left in the same position it started from
+fn parse_line(
+    tokens: &[Token],
+    position: &mut usize,
+    board: &mut Board,
+    is_variation: bool,
+) -> Result<Vec<PgnMove>, PgnError> {
+    let mut moves: Vec<PgnMove> = vec![];
+    let mut pending_comment: Option<String> = None;
+    let mut is_closed = false;
+
+    while *position < tokens.len() {
+        let token = &tokens[*position];
+        *position += 1;
+
+        match token {
+            Token::San(san) => {
+                let bitmove = board.parse_san(san)?;
+                let chess_move = ChessMove::from_bitmove(&bitmove, board);
+                let san = board.san(&bitmove);
+                board.make_move(&bitmove);
+
+                let mut pgn_move = PgnMove::new(chess_move, san, board.fen());
+                pgn_move.comment_before = pending_comment.take();
+                moves.push(pgn_move);
+            }
+            Token::Comment(comment) => match moves.last_mut() {
+                Some(last_move) => append_comment(&mut last_move.comment, comment),
+                None => append_comment(&mut pending_comment, comment),
+            },
+            Token::Nag(nag) => match moves.last_mut() {
+                // "Nf6?? $4" annotates the same thing twice
+                Some(last_move) if last_move.nags.contains(nag) => {}
+                Some(last_move) => last_move.nags.push(*nag),
+                None => return Err(PgnError::UnexpectedToken(format!("${}", nag))),
+            },
+            Token::VariationStart => {
+                if moves.is_empty() {
+                    return Err(PgnError::UnexpectedToken("(".to_string()));
+                }
+
+                // the variation replaces the last move, so it starts from the position before it
+                let last_move = board.unmake_move().unwrap();
+                let variation = parse_line(tokens, position, board, true)?;
+                board.make_move(&last_move);
+
+                moves.last_mut().unwrap().variations.push(variation);
+            }
+            Token::VariationEnd if is_variation => {
+                is_closed = true;
+                break;
+            }
+            Token::VariationEnd => return Err(PgnError::UnbalancedVariation),
+            Token::TagPair(name, _) => return Err(PgnError::UnexpectedToken(format!("[{}", name))),
+            Token::GameResult(result) => return Err(PgnError::UnexpectedToken(result.clone())),
+        }
+    }
+    if is_variation && !is_closed {
+        return Err(PgnError::UnbalancedVariation);
+    }
+
+    for _ in 0..moves.len() {
+        board.unmake_move();
+    }
+    return Ok(moves);
+}
+
+fn append_comment(target: &mut Option<String>, comment: &str) {
+    match target {
+        Some(existing) => {
+            existing.push(' ');
+            existing.push_str(comment);
+        }
+        None => *target = Some(comment.to_string()),
+    }
+}
+
+fn tokenize(pgn: &str) -> Result<Vec<Token>, PgnError> {
+    let mut tokens: Vec<Token> = vec![];
+    let chars: Vec<char> = pgn.chars().collect();
+    let mut idx = 0;
+    let mut is_line_start = true;
+
+    while idx < chars.len() {
+        let c = chars[idx];
+
+        // lines starting with % are escaped, they carry data for other programs
+        if is_line_start && c == '%' {
+            while idx < chars.len() && chars[idx] != '\n' {
+                idx += 1;
+            }
+            continue;
+        }
+        is_line_start = c == '\n';
+
+        match c {
+            c if c.is_whitespace() => idx += 1,
+            '[' => {
+                let end = find_tag_end(&chars, idx)?;
+                let tag: String = chars[idx + 1..end].iter().collect();
+                tokens.push(parse_tag_pair(&tag)?);
+                idx = end + 1;
+            }
+            '{' => {
+                let end = (idx..chars.len())
+                    .find(|&end| chars[end] == '}')
+                    .ok_or(PgnError::UnterminatedComment)?;
+                let comment: String = chars[idx + 1..end].iter().collect();
+                tokens.push(Token::Comment(comment.split_whitespace().collect::<Vec<&str>>().join(" ")));
+                idx = end + 1;
+            }
+            ';' => {
+                let end = (idx..chars.len())
+                    .find(|&end| chars[end] == '\n')
+                    .unwrap_or(chars.len());
+                let comment: String = chars[idx + 1..end].iter().collect();
+                tokens.push(Token::Comment(comment.trim().to_string()));
+                idx = end;
+            }
+            '(' => {
+                tokens.push(Token::VariationStart);
+                idx += 1;
+            }
+            ')' => {
+                tokens.push(Token::VariationEnd);
+                idx += 1;
+            }
+            _ => {
+                let end = (idx..chars.len())
+                    .find(|&end| chars[end].is_whitespace() || "[]{}();".contains(chars[end]))
+                    .unwrap_or(chars.len());
+                let word: String = chars[idx..end].iter().collect();
+                push_word_tokens(&mut tokens, &word)?;
+                idx = end;
+            }
+        }
+    }
+    return Ok(tokens);
+}
+
+// a closing bracket inside the quoted value does not end the tag
+fn find_tag_end(chars: &[char], start: usize) -> Result<usize, PgnError> {
+    let mut in_quotes = false;
+    let mut idx = start + 1;
+    while idx < chars.len() {
+        match chars[idx] {
+            '\\' if in_quotes => idx += 1,
+            '"' => in_quotes = !in_quotes,
+            ']' if !in_quotes => return Ok(idx),
+            '\n' => break,
+            _ => {}
+        }
+        idx += 1;
+    }
+    let tag: String = chars[start..idx.min(chars.len())].iter().collect();
+    return Err(PgnError::InvalidTag(tag));
+}
+
+fn parse_tag_pair(tag: &str) -> Result<Token, PgnError> {
+    let invalid = || PgnError::InvalidTag(tag.to_string());
+    let tag = tag.trim();
+
+    let (name, value) = tag.split_once(char::is_whitespace).ok_or_else(invalid)?;
+    let value = value.trim();
+    if name.is_empty()
+        || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        || value.len() < 2
+        || !value.starts_with('"')
+        || !value.ends_with('"')
+    {
+        return Err(invalid());
+    }
+
+    let mut unescaped = String::new();
+    let mut chars = value[1..value.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            unescaped.push(chars.next().ok_or_else(invalid)?);
+        } else {
+            unescaped.push(c);
+        }
+    }
+    return Ok(Token::TagPair(name.to_string(), unescaped));
+}
+
+fn push_word_tokens(tokens: &mut Vec<Token>, word: &str) -> Result<(), PgnError> {
+    if ["1-0", "0-1", "1/2-1/2", "*"].contains(&word) {
+        tokens.push(Token::GameResult(word.to_string()));
+        return Ok(());
+    }
+    if let Some(nag) = word.strip_prefix('$') {
+        let nag = nag
+            .parse::<u8>()
+            .map_err(|_| PgnError::UnexpectedToken(word.to_string()))?;
+        tokens.push(Token::Nag(nag));
+        return Ok(());
+    }
+
+    // move numbers can be glued to the move itself, e.g. 1.e4 or 12...Nf6, but only digits
+    // followed by a dot are a move number, 0-0 is castling
+    let after_digits = word.trim_start_matches(|c: char| c.is_ascii_digit());
+    let word = if after_digits.is_empty() || after_digits.starts_with('.') {
+        after_digits.trim_start_matches('.')
+    } else {
+        word
+    };
+    if word.is_empty() {
+        return Ok(());
+    }
+
+    let san = word.trim_end_matches(['!', '?']);
+    let suffix = &word[san.len()..];
+    if san.is_empty() {
+        return Err(PgnError::UnexpectedToken(word.to_string()));
+    }
+    tokens.push(Token::San(san.to_string()));
+
+    if !suffix.is_empty() {
+        let nag = SUFFIX_ANNOTATIONS
+            .iter()
+            .find(|(annotation, _)| *annotation == suffix)
+            .ok_or_else(|| PgnError::UnexpectedToken(word.to_string()))?;
+        tokens.push(Token::Nag(nag.1));
+    }
+    return Ok(());
+}
+
+fn write_line(tokens: &mut Vec<String>, starting_fen: &str, moves: &[PgnMove]) {
+    let mut fen_before = starting_fen;
+    let mut needs_move_number = true;
+
+    for pgn_move in moves {
+        if let Some(comment) = &pgn_move.comment_before {
+            tokens.push(format!("{{{}}}", comment));
+            needs_move_number = true;
+        }
+
+        let (move_number, is_white) = get_move_number(fen_before);
+        if is_white {
+            tokens.push(format!("{}.", move_number));
+        } else if needs_move_number {
+            tokens.push(format!("{}...", move_number));
+        }
+        tokens.push(pgn_move.san.clone());
+        needs_move_number = false;
+
+        for nag in pgn_move.nags.iter() {
+            tokens.push(format!("${}", nag));
+        }
+        if let Some(comment) = &pgn_move.comment {
+            tokens.push(format!("{{{}}}", comment));
+            needs_move_number = true;
+        }
+        for variation in pgn_move.variations.iter() {
+            tokens.push("(".to_string());
+            write_line(tokens, fen_before, variation);
+            tokens.push(")".to_string());
+            needs_move_number = true;
+        }
+
+        fen_before = &pgn_move.fen;
+    }
+}
+
+// the fullmove number and whether white is the one to move in the position
+fn get_move_number(fen: &str) -> (u16, bool) {
+    let mut fields = fen.split_whitespace().skip(1);
+    let is_white = fields.next() != Some("b");
+    let move_number = fields.nth(3).and_then(|number| number.parse::<u16>().ok()).unwrap_or(1);
+    return (move_number, is_white);
+}
+
+fn result_token(result: &Option<GameEnd>) -> &'static str {
+    return match result {
+        Some(GameEnd::WhiteWon(_)) => "1-0",
+        Some(GameEnd::BlackWon(_)) => "0-1",
+        Some(GameEnd::Draw(_)) => "1/2-1/2",
+        None => "*",
+    };
+}
+
+fn game_end_reason(result: &GameEnd) -> &str {
+    return match result {
+        GameEnd::WhiteWon(reason) | GameEnd::BlackWon(reason) | GameEnd::Draw(reason) => reason,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANNOTATED_GAME: &str = r#"[Event "Casual game"]
+[Site "Budapest"]
+[Date "2024.05.01"]
+[Round "-"]
+[White "Tamas"]
+[Black "Guest \"the bold\""]
+[Result "1-0"]
+[Termination "Checkmate"]
+
+{Scholar's mate} 1. e4 e5 2. Bc4 {aiming at f7} Nc6 (2... Nf6 3. d3 (3. Nc3 d6) 3... Bc5)
+3. Qh5!? Nf6?? $4 ; the losing move
+4. Qxf7# 1-0
+"#;
+
+    fn sans(moves: &[PgnMove]) -> Vec<&str> {
+        return moves.iter().map(|pgn_move| pgn_move.san.as_str()).collect();
+    }
+
+    #[test]
+    fn parse_annotated_game_test() {
+        let games = parse_pgn(ANNOTATED_GAME).unwrap();
+        assert_eq!(games.len(), 1);
+        let game = &games[0];
+
+        assert_eq!(game.tag("White"), Some("Tamas"));
+        assert_eq!(game.tag("Black"), Some("Guest \"the bold\""));
+        assert_eq!(game.result, Some(GameEnd::WhiteWon("Checkmate".to_string())));
+        assert_eq!(game.starting_fen, START_FEN);
+
+        assert_eq!(sans(&game.moves), vec!["e4", "e5", "Bc4", "Nc6", "Qh5", "Nf6", "Qxf7#"]);
+        assert_eq!(game.moves[0].comment_before.as_deref(), Some("Scholar's mate"));
+        assert_eq!(game.moves[2].comment.as_deref(), Some("aiming at f7"));
+        assert_eq!(game.moves[4].nags, vec![5]);
+        assert_eq!(game.moves[5].nags, vec![4]);
+        assert_eq!(game.moves[5].comment.as_deref(), Some("the losing move"));
+
+        // the variation replaces 2... Nc6 and holds a nested one replacing 3. d3
+        let variation = &game.moves[3].variations[0];
+        assert_eq!(sans(variation), vec!["Nf6", "d3", "Bc5"]);
+        assert_eq!(sans(&variation[1].variations[0]), vec!["Nc3", "d6"]);
+
+        let positions = game.positions();
+        assert_eq!(positions.len(), 8);
+        assert_eq!(
+            positions[7],
+            "r1bqkb1r/pppp1Qpp/2n2n2/4p3/2B1P3/8/PPPP1PPP/RNB1K1NR b KQkq - 0 4"
+        );
+        assert_eq!(game.main_line()[6].notation(), "h5f7");
+    }
+
+    #[test]
+    fn export_test() {
+        let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 3 2";
+        let moves: Vec<ChessMove> = ["Nf6", "Nxe5", "Nxe5", "d4"]
+            .iter()
+            .scan(fen.to_string(), |fen, san| {
+                let chess_move = ChessMove::from_san(san, fen).unwrap();
+                *fen = crate::get_board_after_move(fen, &chess_move).unwrap();
+                Some(chess_move)
+            })
+            .collect();
+
+        let mut game = PgnGame::from_moves(fen, &moves).unwrap();
+        game.set_tag("White", "Alice");
+        game.set_result(Some(GameEnd::Draw("Agreement".to_string())));
+        game.moves[1].comment = Some("a pawn is a pawn".to_string());
+
+        let expected = format!(
+            "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n[White \"Alice\"]\n\
+             [Black \"?\"]\n[Result \"1/2-1/2\"]\n[SetUp \"1\"]\n[FEN \"{}\"]\n[Termination \"Agreement\"]\n\n\
+             2... Nf6 3. Nxe5 {{a pawn is a pawn}} 3... Nxe5 4. d4 1/2-1/2\n",
+            fen
+        );
+        assert_eq!(game.to_pgn(), expected);
+    }
+
+    #[test]
+    fn round_trip_test() {
+        let game = &parse_pgn(ANNOTATED_GAME).unwrap()[0];
+        let exported = game.to_pgn();
+        let movetext = exported.split_whitespace().collect::<Vec<&str>>().join(" ");
+        assert!(movetext.contains("2... Nc6 (2... Nf6 3. d3 (3. Nc3 d6) 3... Bc5) 3. Qh5 $5 Nf6 $4"));
+        assert!(exported.lines().all(|line| line.len() <= MAX_LINE_LENGTH));
+
+        let reimported = &parse_pgn(&exported).unwrap()[0];
+        assert_eq!(reimported.to_pgn(), exported);
+        assert_eq!(reimported.positions(), game.positions());
+    }
+
+    #[test]
+    fn multiple_games_test() {
+        let pgn = "[Event \"first\"]\n\n1. f3 e5 2. g4 Qh4# 0-1\n\n\
+                   [Event \"second\"]\n[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/8/4K2R w K - 0 1\"]\n\n1. O-O *\n\n\
+                   1.e4 c5 2.Nf3";
+        let games = parse_pgn(pgn).unwrap();
+
+        assert_eq!(games.len(), 3);
+        assert_eq!(games[0].result, Some(GameEnd::BlackWon("".to_string())));
+        assert_eq!(games[1].tag("Event"), Some("second"));
+        assert_eq!(games[1].moves[0].fen, "4k3/8/8/8/8/8/8/5RK1 b - - 1 1");
+        assert_eq!(games[1].result, None);
+        assert_eq!(sans(&games[2].moves), vec!["e4", "c5", "Nf3"]);
+    }
+
+    #[test]
+    fn zero_castling_test() {
+        let game = &parse_pgn("1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5 4. 0-0 Nf6 *").unwrap()[0];
+        assert_eq!(game.moves.len(), 8);
+        assert_eq!(game.main_line()[6].notation(), "e1g1");
+
+        // glued to the move number as well
+        let game = &parse_pgn("1. d4 d5 2. Nc3 Nc6 3. Bf4 Bf5 4. Qd2 Qd7 5.0-0-0 5...0-0-0 *")
+            .unwrap()[0];
+        assert_eq!(game.main_line()[8].notation(), "e1c1");
+        assert_eq!(game.main_line()[9].notation(), "e8c8");
+    }
+
+    #[test]
+    fn invalid_pgn_test() {
+        assert_eq!(
+            parse_pgn("1. e4 e5 2. Ke3").unwrap_err(),
+            PgnError::InvalidMove(SanError::IllegalMove("Ke3".to_string()))
+        );
+        assert_eq!(parse_pgn("1. e4 {unfinished").unwrap_err(), PgnError::UnterminatedComment);
+        assert_eq!(parse_pgn("1. e4 (1. d4 d5").unwrap_err(), PgnError::UnbalancedVariation);
+        assert_eq!(parse_pgn("1. e4 e5)").unwrap_err(), PgnError::UnbalancedVariation);
+        assert_eq!(
+            parse_pgn("[Event missing quotes]").unwrap_err(),
+            PgnError::InvalidTag("Event missing quotes".to_string())
+        );
+        assert!(matches!(
+            parse_pgn("[FEN \"8/8/8/8/8/8/8/8 w - - 0 1\"] 1. e4").unwrap_err(),
+            PgnError::InvalidFen(_)
+        ));
+        let e4 = ChessMove::from_san("e4", START_FEN).unwrap();
+        assert!(PgnGame::from_moves(START_FEN, &[e4.clone(), e4]).is_err());
+    }
+}
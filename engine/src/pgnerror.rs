@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::fenerror::FenError;
+use super::sanerror::SanError;
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub enum PgnError {
+    InvalidTag(String),
+    InvalidFen(FenError),
+    InvalidMove(SanError),
+    UnterminatedComment,
+    UnbalancedVariation,
+    UnexpectedToken(String),
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            PgnError::InvalidTag(tag) => write!(f, "invalid tag pair '{}'", tag),
+            PgnError::InvalidFen(e) => write!(f, "invalid starting position: {}", e),
+            PgnError::InvalidMove(e) => write!(f, "invalid move: {}", e),
+            PgnError::UnterminatedComment => write!(f, "comment is never closed"),
+            PgnError::UnbalancedVariation => write!(f, "unbalanced parentheses around a variation"),
+            PgnError::UnexpectedToken(token) => write!(f, "unexpected token '{}'", token),
+        };
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+impl From<FenError> for PgnError {
+    fn from(e: FenError) -> Self {
+        return PgnError::InvalidFen(e);
+    }
+}
+
+impl From<SanError> for PgnError {
+    fn from(e: SanError) -> Self {
+        return PgnError::InvalidMove(e);
+    }
+}
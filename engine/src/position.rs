@@ -0,0 +1,199 @@
+use crate::bitboard::board::Board;
+
+use super::boardsquare::BoardSquare;
+use super::chessmove::ChessMove;
+use super::fenerror::FenError;
+use super::illegalmove::IllegalMove;
+use super::movefilter::MoveFilter;
+use super::piecetype::PieceType;
+
+/// A chess position that keeps its board between calls, unlike the fen based functions of the crate
+/// that rebuild it every time. Moves played on it can be taken back with `undo`.
+#[derive(Clone)]
+pub struct Position {
+    board: Board,
+}
+
+impl Position {
+    /// The standard starting position.
+    pub fn new() -> Self {
+        return Self {
+            board: Board::new(),
+        };
+    }
+
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        return Ok(Self {
+            board: Board::try_from_fen(fen)?,
+        });
+    }
+
+    /// The Fischer Random starting position with the given number (0-959), `None` for other numbers.
+    pub fn chess960(index: u16) -> Option<Self> {
+        return Board::new_chess960(index).map(|board| Self { board });
+    }
+
+    pub fn fen(&self) -> String {
+        return self.board.fen();
+    }
+
+    pub fn is_white_to_move(&self) -> bool {
+        return self.board.side_to_move() == 0;
+    }
+
+    /// Every legal move of the side to move.
+    pub fn legal_moves(&mut self) -> Vec<ChessMove> {
+        return self.moves(MoveFilter::All).collect();
+    }
+
+    /// The legal moves the filter asks for, captures first. The moves are generated up front
+    /// without allocating, the iterator only converts them.
+    pub fn moves(&mut self, filter: MoveFilter) -> impl ExactSizeIterator<Item = ChessMove> + '_ {
+        let moves = self.board.legal_moves(filter);
+        let board = &self.board;
+        return moves.map(move |bitmove| ChessMove::from_bitmove(&bitmove, board));
+    }
+
+    pub fn is_legal(&mut self, chess_move: &ChessMove) -> bool {
+        return chess_move.validate_on_board(&mut self.board).is_ok();
+    }
+
+    /// Plays the move if it is legal in the position, otherwise tells why it was rejected and
+    /// leaves the position as it was.
+    pub fn play(&mut self, chess_move: &ChessMove) -> Result<(), IllegalMove> {
+        let bitmove = chess_move.validate_on_board(&mut self.board)?;
+        self.board.make_move(&bitmove);
+        return Ok(());
+    }
+
+    /// Takes back the last move played with `play`, `None` if there is nothing to take back.
+    pub fn undo(&mut self) -> Option<ChessMove> {
+        let bitmove = self.board.unmake_move()?;
+        return Some(ChessMove::from_bitmove(&bitmove, &self.board));
+    }
+
+    /// Whether the side to move is in check.
+    pub fn is_check(&self) -> bool {
+        return self.board.check_test().check_count > 0;
+    }
+
+    pub fn piece_at(&self, square: BoardSquare) -> Option<PieceType> {
+        if square.x > 7 || square.y > 7 {
+            return None;
+        }
+        return match self.board.piece_board(square.to_index()) {
+            Board::EMPTY_SQUARE => None,
+            piece => Some(PieceType::from_index(piece)),
+        };
+    }
+
+    /// Every piece on the board with its square, from a1 to h8.
+    pub fn pieces(&self) -> impl Iterator<Item = (BoardSquare, PieceType)> + '_ {
+        return (0..64u8).filter_map(|sq| match self.board.piece_board(sq) {
+            Board::EMPTY_SQUARE => None,
+            piece => Some((BoardSquare::from_index(sq), PieceType::from_index(piece))),
+        });
+    }
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::piecetype::PieceType::*;
+
+    #[test]
+    fn legal_moves_test() {
+        let mut position = Position::new();
+        assert_eq!(position.legal_moves().len(), 20);
+        assert!(position.is_white_to_move());
+
+        let mut position = Position::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        let moves = position.legal_moves();
+        assert_eq!(moves.len(), 15);
+        assert!(moves
+            .iter()
+            .any(|chess_move| matches!(chess_move, ChessMove::Castle { .. })));
+
+        assert_eq!(position.moves(MoveFilter::Captures).len(), 0);
+        assert_eq!(position.moves(MoveFilter::Checks).len(), 1);
+
+        assert!(Position::from_fen("8/8/8/8/8/8/8/8 w - - 0 1").is_err());
+        assert!(Position::chess960(960).is_none());
+        assert_eq!(
+            Position::chess960(518).unwrap().fen(),
+            Position::default().fen()
+        );
+    }
+
+    #[test]
+    fn play_and_undo_test() {
+        let mut position = Position::new();
+        let e4 = ChessMove::from_san("e4", &position.fen()).unwrap();
+        let e5 = ChessMove::quiet(
+            BlackPawn,
+            BoardSquare::from_coord(4, 6),
+            BoardSquare::from_coord(4, 4),
+            None,
+        );
+
+        assert_eq!(
+            position.play(&e5),
+            Err(IllegalMove::OpponentPiece("e7e5".to_string()))
+        );
+        assert!(position.play(&e4).is_ok());
+        assert!(!position.is_white_to_move());
+        assert_eq!(
+            position.fen(),
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+        );
+
+        assert_eq!(position.undo().unwrap().notation(), "e2e4");
+        assert!(position.undo().is_none());
+        assert_eq!(position.fen(), Position::new().fen());
+    }
+
+    #[test]
+    fn is_check_test() {
+        let mut position = Position::new();
+        for san in ["f3", "e5", "g4", "Qh4#"] {
+            assert!(!position.is_check());
+            let chess_move = ChessMove::from_san(san, &position.fen()).unwrap();
+            assert!(position.play(&chess_move).is_ok());
+        }
+        assert!(position.is_check());
+        assert!(position.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn pieces_test() {
+        let position = Position::from_fen("4k3/8/8/8/8/8/3P4/4K2R w K - 0 1").unwrap();
+
+        assert_eq!(
+            position.piece_at(BoardSquare::from_coord(3, 1)),
+            Some(WhitePawn)
+        );
+        assert_eq!(
+            position.piece_at(BoardSquare::from_coord(4, 7)),
+            Some(BlackKing)
+        );
+        assert_eq!(position.piece_at(BoardSquare::from_coord(0, 0)), None);
+
+        let pieces: Vec<(BoardSquare, PieceType)> = position.pieces().collect();
+        assert_eq!(
+            pieces,
+            vec![
+                (BoardSquare::from_coord(4, 0), WhiteKing),
+                (BoardSquare::from_coord(7, 0), WhiteRook),
+                (BoardSquare::from_coord(3, 1), WhitePawn),
+                (BoardSquare::from_coord(4, 7), BlackKing),
+            ]
+        );
+        assert_eq!(Position::new().pieces().count(), 32);
+    }
+}
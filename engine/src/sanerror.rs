@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::fenerror::FenError;
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub enum SanError {
+    InvalidFen(FenError),
+    InvalidSan(String),
+    IllegalMove(String),
+    AmbiguousMove(String),
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            SanError::InvalidFen(e) => write!(f, "invalid fen: {}", e),
+            SanError::InvalidSan(san) => write!(f, "'{}' is not valid algebraic notation", san),
+            SanError::IllegalMove(san) => write!(f, "'{}' is not a legal move", san),
+            SanError::AmbiguousMove(san) => {
+                write!(f, "'{}' matches more than one legal move", san)
+            }
+        };
+    }
+}
+
+impl std::error::Error for SanError {}
+
+impl From<FenError> for SanError {
+    fn from(e: FenError) -> Self {
+        return SanError::InvalidFen(e);
+    }
+}
@@ -0,0 +1,499 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::bitboard::bitmove::{BitMove, BitMoveType};
+use crate::bitboard::board::Board;
+use crate::bitboard::movebuffer::MoveBuffer;
+use crate::chessmove::ChessMove;
+use crate::movefilter::MoveFilter;
+
+pub const MAX_DEPTH: u8 = 64;
+pub const MATE_SCORE: i32 = 30000;
+const INFINITY: i32 = 32000;
+
+// the search stops at whichever limit is reached first, without any limit it runs to MAX_DEPTH
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SearchLimits {
+    pub depth: Option<u8>,
+    pub nodes: Option<u64>,
+    pub movetime: Option<Duration>,
+    // lets another thread end the search early, e.g. on the uci stop command
+    #[serde(skip)]
+    pub stop: Option<Arc<AtomicBool>>,
+}
+
+impl SearchLimits {
+    pub fn depth(depth: u8) -> Self {
+        return Self {
+            depth: Some(depth),
+            ..Default::default()
+        };
+    }
+    pub fn nodes(nodes: u64) -> Self {
+        return Self {
+            nodes: Some(nodes),
+            ..Default::default()
+        };
+    }
+    pub fn movetime(movetime: Duration) -> Self {
+        return Self {
+            movetime: Some(movetime),
+            ..Default::default()
+        };
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SearchResult {
+    // None only if the side to move has no legal moves
+    pub best_move: Option<ChessMove>,
+    // centipawns from the side to move's perspective, see mate_in for mate scores
+    pub score: i32,
+    pub principal_variation: Vec<ChessMove>,
+    // the last fully searched iteration
+    pub depth: u8,
+    pub seldepth: u8,
+    pub nodes: u64,
+    pub quiescence_nodes: u64,
+    pub elapsed: Duration,
+}
+
+impl SearchResult {
+    // moves until mate, negative if the side to move is getting mated
+    pub fn mate_in(&self) -> Option<i32> {
+        if self.score.abs() < MATE_SCORE - MAX_DEPTH as i32 * 2 {
+            return None;
+        }
+        let plies = MATE_SCORE - self.score.abs();
+        let moves = (plies + 1) / 2;
+        return Some(if self.score > 0 { moves } else { -moves });
+    }
+
+    pub fn nodes_per_second(&self) -> u64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds == 0.0 {
+            return 0;
+        }
+        return (self.nodes as f64 / seconds) as u64;
+    }
+}
+
+const PIECE_VALUES: [i32; 6] = [100, 320, 330, 500, 900, 0];
+const MAX_PLY: usize = MAX_DEPTH as usize + 32;
+const CHECK_INTERVAL: u64 = 1024;
+
+pub(crate) struct Searcher {
+    limits: SearchLimits,
+    start_time: Instant,
+    nodes: u64,
+    quiescence_nodes: u64,
+    seldepth: usize,
+    is_stopped: bool,
+    // hashes of every position on the current line, for repetition detection
+    hash_history: Vec<u64>,
+    // hashes of the positions the game went through before the root
+    game_history: Vec<u64>,
+    pv_table: [[BitMove; MAX_PLY]; MAX_PLY],
+    pv_length: [usize; MAX_PLY],
+    previous_pv: Vec<BitMove>,
+}
+
+impl Searcher {
+    pub(crate) fn new(limits: SearchLimits) -> Self {
+        return Self {
+            limits,
+            start_time: Instant::now(),
+            nodes: 0,
+            quiescence_nodes: 0,
+            seldepth: 0,
+            is_stopped: false,
+            hash_history: Vec::with_capacity(MAX_PLY),
+            game_history: Vec::new(),
+            pv_table: [[BitMove::quiet(0, 0, None); MAX_PLY]; MAX_PLY],
+            pv_length: [0; MAX_PLY],
+            previous_pv: Vec::new(),
+        };
+    }
+
+    // oldest first, the last one is the position right before the root
+    pub(crate) fn set_game_history(&mut self, game_history: Vec<u64>) {
+        self.game_history = game_history;
+    }
+
+    pub(crate) fn search(&mut self, board: &mut Board) -> SearchResult {
+        return self.search_with_callback(board, &mut |_| {});
+    }
+
+    // on_iteration is called with the intermediate result after every completed iteration
+    pub(crate) fn search_with_callback(
+        &mut self,
+        board: &mut Board,
+        on_iteration: &mut dyn FnMut(&SearchResult),
+    ) -> SearchResult {
+        self.start_time = Instant::now();
+        let max_depth = self.limits.depth.unwrap_or(MAX_DEPTH).clamp(1, MAX_DEPTH);
+
+        let mut result = SearchResult {
+            best_move: None,
+            score: 0,
+            principal_variation: vec![],
+            depth: 0,
+            seldepth: 0,
+            nodes: 0,
+            quiescence_nodes: 0,
+            elapsed: Duration::ZERO,
+        };
+
+        for depth in 1..=max_depth {
+            self.seldepth = 0;
+            self.hash_history.clone_from(&self.game_history);
+            let score = self.negamax(board, depth as i32, 0, -INFINITY, INFINITY, true);
+
+            // an unfinished iteration is thrown away, except for the first one,
+            // which is never interrupted
+            if self.is_stopped {
+                break;
+            }
+
+            self.previous_pv = self.pv_table[0][..self.pv_length[0]].to_vec();
+            result.score = score;
+            result.depth = depth;
+            result.seldepth = self.seldepth as u8;
+            result.principal_variation = self.pv_to_chess_moves(board);
+            result.best_move = result.principal_variation.first().cloned();
+            result.nodes = self.nodes;
+            result.quiescence_nodes = self.quiescence_nodes;
+            result.elapsed = self.start_time.elapsed();
+            on_iteration(&result);
+
+            if self.should_stop() || result.mate_in().is_some() {
+                break;
+            }
+        }
+
+        result.nodes = self.nodes;
+        result.quiescence_nodes = self.quiescence_nodes;
+        result.elapsed = self.start_time.elapsed();
+        return result;
+    }
+
+    fn negamax(&mut self, board: &mut Board, depth: i32, ply: usize, mut alpha: i32, beta: i32, is_pv_line: bool) -> i32 {
+        self.pv_length[ply] = 0;
+
+        if depth <= 0 || ply >= MAX_PLY - 1 {
+            return self.quiescence(board, ply, alpha, beta);
+        }
+
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply);
+        if self.nodes.is_multiple_of(CHECK_INTERVAL) && !self.previous_pv.is_empty() && self.should_stop() {
+            self.is_stopped = true;
+        }
+        if self.is_stopped {
+            return 0;
+        }
+
+        if ply > 0 && self.is_draw(board) {
+            return 0;
+        }
+
+        let mut buffer = MoveBuffer::new();
+        let in_check = board.generate_moves(&mut buffer, MoveFilter::All);
+
+        if buffer.count() == 0 {
+            return if in_check { -MATE_SCORE + ply as i32 } else { 0 };
+        }
+
+        // the previous principal variation only helps while the current line still follows it
+        let pv_move = if is_pv_line { self.previous_pv.get(ply).copied() } else { None };
+        let mut move_scores = [0i32; 256];
+        Self::score_moves(board, &buffer, &mut move_scores, pv_move);
+
+        self.hash_history.push(board.hash());
+        for idx in 0..buffer.count() {
+            let next_move = Self::pick_next_move(&mut buffer, &mut move_scores, idx);
+            let is_next_pv_line = pv_move == Some(next_move);
+
+            board.make_move(&next_move);
+            let score = -self.negamax(board, depth - 1, ply + 1, -beta, -alpha, is_next_pv_line);
+            board.unmake_move();
+
+            if self.is_stopped {
+                self.hash_history.pop();
+                return 0;
+            }
+            if score > alpha {
+                alpha = score;
+                self.update_pv(ply, next_move);
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+        self.hash_history.pop();
+
+        return alpha;
+    }
+
+    fn quiescence(&mut self, board: &mut Board, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.pv_length[ply] = 0;
+        self.nodes += 1;
+        self.quiescence_nodes += 1;
+        self.seldepth = self.seldepth.max(ply);
+        if self.nodes.is_multiple_of(CHECK_INTERVAL) && !self.previous_pv.is_empty() && self.should_stop() {
+            self.is_stopped = true;
+        }
+        if self.is_stopped {
+            return 0;
+        }
+
+        let mut buffer = MoveBuffer::new();
+        let in_check = board.generate_moves(&mut buffer, MoveFilter::All);
+
+        if buffer.count() == 0 {
+            return if in_check { -MATE_SCORE + ply as i32 } else { 0 };
+        }
+        if ply >= MAX_PLY - 1 {
+            return board.evaluate();
+        }
+
+        // standing pat is not an option while in check, every evasion gets searched instead
+        if !in_check {
+            let stand_pat = board.evaluate();
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat);
+        }
+
+        let mut move_scores = [0i32; 256];
+        Self::score_moves(board, &buffer, &mut move_scores, None);
+
+        for idx in 0..buffer.count() {
+            let next_move = Self::pick_next_move(&mut buffer, &mut move_scores, idx);
+            if !in_check && !Self::is_tactical(&next_move) {
+                continue;
+            }
+
+            board.make_move(&next_move);
+            let score = -self.quiescence(board, ply + 1, -beta, -alpha);
+            board.unmake_move();
+
+            if self.is_stopped {
+                return 0;
+            }
+            if score > alpha {
+                alpha = score;
+                self.update_pv(ply, next_move);
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        return alpha;
+    }
+
+    fn should_stop(&self) -> bool {
+        if self.limits.nodes.is_some_and(|nodes| self.nodes >= nodes) {
+            return true;
+        }
+        if self
+            .limits
+            .stop
+            .as_ref()
+            .is_some_and(|stop| stop.load(Ordering::Relaxed))
+        {
+            return true;
+        }
+        return self
+            .limits
+            .movetime
+            .is_some_and(|movetime| self.start_time.elapsed() >= movetime);
+    }
+
+    fn is_draw(&self, board: &Board) -> bool {
+        if board.halfmove_clock() >= 100 || board.is_insufficient_material() {
+            return true;
+        }
+
+        // only positions since the last capture or pawn move can repeat,
+        // and only every second one has the same side to move
+        let hash = board.hash();
+        let reversible_plies = board.halfmove_clock() as usize;
+        return self
+            .hash_history
+            .iter()
+            .rev()
+            .skip(1)
+            .step_by(2)
+            .take(reversible_plies / 2)
+            .any(|&previous| previous == hash);
+    }
+
+    fn is_tactical(bitmove: &BitMove) -> bool {
+        return match bitmove.move_type() {
+            BitMoveType::Capture | BitMoveType::EnPassant => true,
+            _ => bitmove.promotion_piece().is_some(),
+        };
+    }
+
+    // previous principal variation first, then captures by MVV-LVA, then promotions, then quiets
+    fn score_moves(board: &Board, buffer: &MoveBuffer, move_scores: &mut [i32; 256], pv_move: Option<BitMove>) {
+        for idx in 0..buffer.count() {
+            let bitmove = buffer.get(idx);
+            let attacker = board.piece_board(bitmove.from_square()) as usize % 6;
+
+            move_scores[idx] = match bitmove.move_type() {
+                BitMoveType::Capture => {
+                    let victim = board.piece_board(bitmove.to_square()) as usize % 6;
+                    100_000 + 10 * PIECE_VALUES[victim] - PIECE_VALUES[attacker]
+                }
+                BitMoveType::EnPassant => 100_000 + 10 * PIECE_VALUES[0] - PIECE_VALUES[0],
+                _ => 0,
+            };
+            if let Some(promotion_piece) = bitmove.promotion_piece() {
+                move_scores[idx] += 50_000 + PIECE_VALUES[promotion_piece as usize];
+            }
+            if pv_move == Some(*bitmove) {
+                move_scores[idx] = 1_000_000;
+            }
+        }
+    }
+
+    // selection sort step, moves the best remaining move to idx
+    fn pick_next_move(buffer: &mut MoveBuffer, move_scores: &mut [i32; 256], idx: usize) -> BitMove {
+        let mut best_idx = idx;
+        for next_idx in idx + 1..buffer.count() {
+            if move_scores[next_idx] > move_scores[best_idx] {
+                best_idx = next_idx;
+            }
+        }
+        move_scores.swap(idx, best_idx);
+        buffer.swap(idx, best_idx);
+        return *buffer.get(idx);
+    }
+
+    fn update_pv(&mut self, ply: usize, bitmove: BitMove) {
+        self.pv_table[ply][ply] = bitmove;
+        let child_length = self.pv_length[ply + 1];
+        for next_ply in ply + 1..ply + 1 + child_length {
+            self.pv_table[ply][next_ply] = self.pv_table[ply + 1][next_ply];
+        }
+        self.pv_length[ply] = child_length + 1;
+    }
+
+    fn pv_to_chess_moves(&self, board: &mut Board) -> Vec<ChessMove> {
+        let mut chess_moves: Vec<ChessMove> = vec![];
+        for bitmove in self.pv_table[0][..self.pv_length[0]].iter() {
+            chess_moves.push(ChessMove::from_bitmove(bitmove, board));
+            board.make_move(bitmove);
+        }
+        for _ in 0..chess_moves.len() {
+            board.unmake_move();
+        }
+        return chess_moves;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(fen: &str, limits: SearchLimits) -> SearchResult {
+        let mut board = Board::build(fen);
+        let result = Searcher::new(limits).search(&mut board);
+        assert_eq!(board.fen(), fen);
+        return result;
+    }
+
+    #[test]
+    fn mate_in_one_test() {
+        let result = search("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", SearchLimits::depth(3));
+
+        assert_eq!(result.best_move.as_ref().unwrap().notation(), "a1a8");
+        assert_eq!(result.mate_in(), Some(1));
+    }
+
+    #[test]
+    fn mated_test() {
+        let result = search("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1", SearchLimits::depth(3));
+
+        assert!(result.best_move.is_none());
+        assert_eq!(result.score, -MATE_SCORE);
+    }
+
+    #[test]
+    fn mate_in_two_test() {
+        // rook ladder, 1. Ra7 Kf8 2. Rb8#
+        let result = search("6k1/8/8/8/8/8/R7/1R4K1 w - - 0 1", SearchLimits::depth(4));
+
+        assert_eq!(result.mate_in(), Some(2));
+        assert_eq!(result.principal_variation.len(), 3);
+    }
+
+    #[test]
+    fn hanging_piece_test() {
+        let result = search("4k3/8/8/3q4/8/8/3R4/3K4 w - - 0 1", SearchLimits::depth(2));
+
+        assert_eq!(result.best_move.as_ref().unwrap().notation(), "d2d5");
+        assert!(result.score > 400);
+    }
+
+    #[test]
+    fn quiescence_test() {
+        // the knight on e5 is defended, taking it with the queen loses the queen
+        let result = search("4k3/8/3p4/4n3/8/8/8/4QK2 w - - 0 1", SearchLimits::depth(1));
+
+        assert_ne!(result.best_move.as_ref().unwrap().notation(), "e1e5");
+        assert!(result.quiescence_nodes > 0);
+    }
+
+    #[test]
+    fn stalemate_test() {
+        let result = search("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1", SearchLimits::depth(3));
+
+        assert!(result.best_move.is_none());
+        assert_eq!(result.score, 0);
+    }
+
+    #[test]
+    fn limits_test() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+        let result = search(fen, SearchLimits::depth(3));
+        assert_eq!(result.depth, 3);
+        assert!(!result.principal_variation.is_empty());
+
+        let result = search(fen, SearchLimits::nodes(5000));
+        assert!(result.best_move.is_some());
+        assert!(result.nodes < 5000 + CHECK_INTERVAL);
+
+        let result = search(fen, SearchLimits::movetime(Duration::from_millis(100)));
+        assert!(result.best_move.is_some());
+        assert!(result.elapsed < Duration::from_millis(1000));
+
+        // an already raised stop flag still lets the first iteration finish
+        let limits = SearchLimits {
+            stop: Some(Arc::new(AtomicBool::new(true))),
+            ..Default::default()
+        };
+        let result = search(fen, limits);
+        assert_eq!(result.depth, 1);
+        assert!(result.best_move.is_some());
+    }
+
+    #[test]
+    fn callback_test() {
+        let mut board = Board::new();
+        let mut depths: Vec<u8> = vec![];
+        let result = Searcher::new(SearchLimits::depth(4))
+            .search_with_callback(&mut board, &mut |result| depths.push(result.depth));
+
+        assert_eq!(depths, vec![1, 2, 3, 4]);
+        assert_eq!(result.depth, 4);
+    }
+}
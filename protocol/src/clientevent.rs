@@ -0,0 +1,152 @@
+use engine::chessmove::ChessMove;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::timecontrol::TimeControl;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type")]
+pub enum ClientEvent {
+    Join {
+        username: String,
+        // clients from before versioning do not send it, they are treated as version 0
+        #[serde(default)]
+        protocol_version: u32,
+        // the token of an earlier session, to get back into a match after a disconnect
+        #[serde(default)]
+        session_token: Option<Uuid>,
+    },
+    // only players asking for the same time control are paired
+    FindMatch {
+        #[serde(default)]
+        time_control: TimeControl,
+    },
+    // the side to move is tracked by the server, the move is all it needs
+    Move {
+        step: ChessMove,
+    },
+    Resign,
+    // answered by the opponent with AcceptDraw or DeclineDraw
+    OfferDraw,
+    AcceptDraw,
+    DeclineDraw,
+    // answered by the opponent with AcceptTakeback or DeclineTakeback
+    RequestTakeback,
+    AcceptTakeback,
+    DeclineTakeback,
+    // ends the game under the fifty-move or threefold repetition rule, if either applies
+    ClaimDraw,
+    // relayed to the match as a ChatMessage
+    Chat {
+        text: String,
+    },
+    RequestLegalMoves {
+        fen: String,
+    },
+    CloseConnection,
+    // anything with a type this version does not know, so it can be answered with an error
+    #[serde(other)]
+    Unknown,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PROTOCOL_VERSION;
+    use engine::boardsquare::BoardSquare;
+    use engine::piecetype::PieceType;
+
+    fn round_trip(event: &ClientEvent) -> String {
+        let json = serde_json::to_string(event).unwrap();
+        let parsed: ClientEvent = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&parsed).unwrap(), json);
+        json
+    }
+
+    #[test]
+    fn test_client_event_round_trip() {
+        let events = [
+            ClientEvent::Join {
+                username: "test".to_string(),
+                protocol_version: PROTOCOL_VERSION,
+                session_token: None,
+            },
+            ClientEvent::FindMatch {
+                time_control: TimeControl::Fischer {
+                    base_ms: 300_000,
+                    increment_ms: 3_000,
+                },
+            },
+            ClientEvent::Move {
+                step: ChessMove::quiet(
+                    PieceType::WhitePawn,
+                    BoardSquare::from_coord(4, 1),
+                    BoardSquare::from_coord(4, 3),
+                    None,
+                ),
+            },
+            ClientEvent::Resign,
+            ClientEvent::OfferDraw,
+            ClientEvent::AcceptDraw,
+            ClientEvent::DeclineDraw,
+            ClientEvent::RequestTakeback,
+            ClientEvent::AcceptTakeback,
+            ClientEvent::DeclineTakeback,
+            ClientEvent::ClaimDraw,
+            ClientEvent::Chat {
+                text: "good luck".to_string(),
+            },
+            ClientEvent::RequestLegalMoves {
+                fen: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string(),
+            },
+            ClientEvent::CloseConnection,
+        ];
+
+        for event in &events {
+            round_trip(event);
+        }
+        assert_eq!(
+            round_trip(&events[0]),
+            r#"{"type":"Join","username":"test","protocol_version":7,"session_token":null}"#
+        );
+        assert_eq!(
+            round_trip(&events[1]),
+            r#"{"type":"FindMatch","time_control":{"Fischer":{"base_ms":300000,"increment_ms":3000}}}"#
+        );
+    }
+
+    #[test]
+    fn test_join_without_version() {
+        let event: ClientEvent =
+            serde_json::from_str(r#"{"type":"Join","username":"old"}"#).unwrap();
+        match event {
+            ClientEvent::Join {
+                username,
+                protocol_version,
+                session_token,
+            } => {
+                assert_eq!(username, "old");
+                assert_eq!(protocol_version, 0);
+                assert_eq!(session_token, None);
+            }
+            _ => panic!("Expected Join event"),
+        }
+    }
+
+    #[test]
+    fn test_find_match_without_time_control() {
+        let event: ClientEvent = serde_json::from_str(r#"{"type":"FindMatch"}"#).unwrap();
+        assert!(matches!(
+            event,
+            ClientEvent::FindMatch {
+                time_control: TimeControl::Unlimited
+            }
+        ));
+    }
+
+    #[test]
+    fn test_unknown_event() {
+        let event: ClientEvent = serde_json::from_str(r#"{"type":"Teleport"}"#).unwrap();
+        assert!(matches!(event, ClientEvent::Unknown));
+    }
+}
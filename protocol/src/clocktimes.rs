@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+
+// remaining time of both players when the message was sent
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ClockTimes {
+    pub white_ms: u64,
+    pub black_ms: u64,
+}
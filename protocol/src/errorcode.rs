@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// what went wrong, so clients can react without parsing the message text
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ErrorCode {
+    MalformedMessage,
+    UnknownEvent,
+    IncompatibleVersion,
+    InvalidSession,
+    NotJoined,
+    AlreadyJoined,
+    UsernameTaken,
+    NotInMatch,
+    NotAPlayer,
+    NotYourTurn,
+    OutOfTime,
+    IllegalMove,
+    InvalidFen,
+    OfferPending,
+    NoPendingOffer,
+    NothingToTakeBack,
+    NoDrawToClaim,
+    InvalidChatMessage,
+    RateLimited,
+    Muted,
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
@@ -0,0 +1,17 @@
+pub mod clientevent;
+pub mod clocktimes;
+pub mod errorcode;
+pub mod offer;
+pub mod servermessage;
+pub mod timecontrol;
+
+pub use clientevent::ClientEvent;
+pub use clocktimes::ClockTimes;
+pub use errorcode::ErrorCode;
+pub use offer::Offer;
+pub use servermessage::ServerMessage2;
+pub use timecontrol::TimeControl;
+
+// bumped on every change to the wire types, the client sends it in Join and the server
+// refuses clients that speak a different version
+pub const PROTOCOL_VERSION: u32 = 7;
@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+// what one player asked the other for, it stays open until answered or the next move
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Offer {
+    Draw,
+    // undoes the last move of the player who asked, and the reply to it if there was one
+    Takeback,
+}
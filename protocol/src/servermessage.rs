@@ -0,0 +1,144 @@
+use engine::chessmove::ChessMove;
+use engine::gameend::GameEnd;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::clocktimes::ClockTimes;
+use super::errorcode::ErrorCode;
+use super::offer::Offer;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub enum ServerMessage2 {
+    GameEnd {
+        winner: GameEnd,
+    },
+    UIUpdate {
+        fen: String,
+        turn_player: String,
+        move_history: Vec<String>,
+        // None in games without a time control
+        #[serde(default)]
+        clock: Option<ClockTimes>,
+    },
+    MatchFound {
+        match_id: Uuid,
+        color: String,
+        opponent_name: String,
+    },
+    LegalMoves {
+        moves: Vec<ChessMove>,
+    },
+    Ok {
+        response: Result<(), String>,
+    },
+    Error {
+        code: ErrorCode,
+        message: String,
+    },
+    // answer to Join, the token lets the client reattach to its match after a disconnect
+    Session {
+        token: Uuid,
+    },
+    // the opponent has timeout_ms to come back before the game is awarded to you
+    OpponentDisconnected {
+        timeout_ms: u64,
+    },
+    OpponentReconnected,
+    // sent to both players, color is the side that made the offer
+    OfferMade {
+        offer: Offer,
+        color: String,
+    },
+    OfferDeclined {
+        offer: Offer,
+    },
+    // the offer was still open when the next move was played
+    OfferExpired {
+        offer: Offer,
+    },
+    // timestamp is in milliseconds since the unix epoch, taken when the server relayed it
+    ChatMessage {
+        from: String,
+        text: String,
+        timestamp: u64,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engine::boardsquare::BoardSquare;
+    use engine::piecetype::PieceType;
+
+    #[test]
+    fn test_server_message_round_trip() {
+        let messages = [
+            ServerMessage2::GameEnd {
+                winner: GameEnd::WhiteWon("Checkmate".to_string()),
+            },
+            ServerMessage2::UIUpdate {
+                fen: "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1".to_string(),
+                turn_player: "black".to_string(),
+                move_history: vec!["e4".to_string()],
+                clock: Some(ClockTimes {
+                    white_ms: 297_500,
+                    black_ms: 300_000,
+                }),
+            },
+            ServerMessage2::MatchFound {
+                match_id: Uuid::nil(),
+                color: "white".to_string(),
+                opponent_name: "Opponent".to_string(),
+            },
+            ServerMessage2::LegalMoves {
+                moves: vec![ChessMove::quiet(
+                    PieceType::WhiteKnight,
+                    BoardSquare::from_coord(6, 0),
+                    BoardSquare::from_coord(5, 2),
+                    None,
+                )],
+            },
+            ServerMessage2::Ok { response: Ok(()) },
+            ServerMessage2::Error {
+                code: ErrorCode::NotYourTurn,
+                message: "it is not your turn".to_string(),
+            },
+            ServerMessage2::Session { token: Uuid::nil() },
+            ServerMessage2::OpponentDisconnected { timeout_ms: 60_000 },
+            ServerMessage2::OpponentReconnected,
+            ServerMessage2::OfferMade {
+                offer: Offer::Takeback,
+                color: "black".to_string(),
+            },
+            ServerMessage2::OfferDeclined { offer: Offer::Draw },
+            ServerMessage2::OfferExpired { offer: Offer::Draw },
+            ServerMessage2::ChatMessage {
+                from: "player".to_string(),
+                text: "good game".to_string(),
+                timestamp: 1_700_000_000_000,
+            },
+        ];
+
+        for message in &messages {
+            let json = serde_json::to_string(message).unwrap();
+            let parsed: ServerMessage2 = serde_json::from_str(&json).unwrap();
+            assert_eq!(serde_json::to_string(&parsed).unwrap(), json);
+        }
+    }
+
+    #[test]
+    fn test_server_message_wire_format() {
+        let message: ServerMessage2 =
+            serde_json::from_str(r#"{"Ok":{"response":{"Ok":null}}}"#).unwrap();
+        assert!(matches!(message, ServerMessage2::Ok { response: Ok(()) }));
+
+        let message = ServerMessage2::Error {
+            code: ErrorCode::IncompatibleVersion,
+            message: "server speaks protocol 1".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_string(&message).unwrap(),
+            r#"{"Error":{"code":"IncompatibleVersion","message":"server speaks protocol 1"}}"#
+        );
+    }
+}
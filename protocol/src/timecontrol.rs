@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+// how much thinking time the players get, chosen when looking for a match
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum TimeControl {
+    #[default]
+    Unlimited,
+    // base time with an increment added after every move
+    Fischer {
+        base_ms: u64,
+        increment_ms: u64,
+    },
+    // the time used for a move is given back after it, up to the delay
+    Bronstein {
+        base_ms: u64,
+        delay_ms: u64,
+    },
+    // the clock only starts running once the delay of the move has passed
+    SimpleDelay {
+        base_ms: u64,
+        delay_ms: u64,
+    },
+    // every move has to be made within the given number of days, unused time is not kept
+    Correspondence {
+        days_per_move: u32,
+    },
+}
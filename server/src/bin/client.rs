@@ -1,41 +1,11 @@
-use engine::gameend::GameEnd;
-use engine::{boardsquare::BoardSquare, chessmove::ChessMove, piecetype::PieceType};
+use engine::{boardsquare::BoardSquare, chessmove::ChessMove};
 use futures_util::{SinkExt, StreamExt};
-use serde::{Deserialize, Serialize};
+use protocol::{ClientEvent, PROTOCOL_VERSION, ServerMessage2, TimeControl};
 use std::io::{self, Write};
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 use url::Url;
 use uuid::Uuid;
 
-#[derive(Serialize, Deserialize)]
-#[serde(tag = "type")]
-enum ClientMessage {
-    Join { username: String },
-    FindMatch,
-    Move { step: ChessMove, fen: String },
-    Resign,
-    Chat { text: String },
-    RequestLegalMoves { fen: String },
-}
-
-#[derive(Serialize, Deserialize)]
-pub enum ServerMessage2 {
-    GameEnd {
-        winner: GameEnd,
-    },
-    UIUpdate {
-        fen: String,
-    },
-    MatchFound {
-        match_id: Uuid,
-        color: String,
-        opponent_name: String,
-    },
-    Ok {
-        response: Result<(), String>,
-    },
-}
-
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Knightly Chess Client");
@@ -101,10 +71,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Main loop for sending messages
     println!("\nAvailable commands:");
     println!("  join <username>    - Join the server");
-    println!("  findmatch          - Find a match");
+    println!("  rejoin <token> <username> - Get back into a match after a disconnect");
+    println!("  findmatch [m+s]    - Find a match (e.g., findmatch 5+3)");
     println!("  move <from> <to>   - Make a move (e.g., move e2 e4)");
     println!("  chat <message>     - Send chat message");
     println!("  resign             - Resign from current game");
+    println!("  draw [offer|accept|decline|claim] - Offer, answer or claim a draw");
+    println!("  takeback [request|accept|decline] - Ask for or answer a takeback");
     println!("  quit               - Exit client");
     println!();
 
@@ -131,14 +104,38 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             "join" => {
                 if parts.len() >= 2 {
                     let username = parts[1..].join(" ");
-                    let message = ClientMessage::Join { username };
+                    let message = ClientEvent::Join {
+                        username,
+                        protocol_version: PROTOCOL_VERSION,
+                        session_token: None,
+                    };
                     send_message(&mut write, &message).await?;
                 } else {
                     println!("Usage: join <username>");
                 }
             }
+            "rejoin" => {
+                match (
+                    parts.get(1).and_then(|token| token.parse::<Uuid>().ok()),
+                    parts.len() >= 3,
+                ) {
+                    (Some(token), true) => {
+                        let message = ClientEvent::Join {
+                            username: parts[2..].join(" "),
+                            protocol_version: PROTOCOL_VERSION,
+                            session_token: Some(token),
+                        };
+                        send_message(&mut write, &message).await?;
+                    }
+                    _ => println!("Usage: rejoin <session token> <username>"),
+                }
+            }
             "findmatch" | "find" => {
-                let message = ClientMessage::FindMatch;
+                let time_control = parts
+                    .get(1)
+                    .and_then(|text| parse_time_control(text))
+                    .unwrap_or_default();
+                let message = ClientEvent::FindMatch { time_control };
                 send_message(&mut write, &message).await?;
                 println!("🔍 Searching for a match...");
             }
@@ -146,7 +143,6 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 if parts.len() >= 3 {
                     //let from = parts[1].to_string();
                     //let to = parts[2].to_string();
-                    let fen = parts[1].to_string();
 
                     let step = ChessMove::quiet(
                         engine::piecetype::PieceType::WhiteBishop,
@@ -155,7 +151,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         None,
                     );
 
-                    let message = ClientMessage::Move { step, fen };
+                    let message = ClientEvent::Move { step };
                     send_message(&mut write, &message).await?;
                     //println!("♟️  Sent move: {} -> {}", parts[1], parts[2]);
                 } else {
@@ -165,24 +161,49 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             "chat" => {
                 if parts.len() >= 2 {
                     let text = parts[1..].join(" ");
-                    let message = ClientMessage::Chat { text };
+                    let message = ClientEvent::Chat { text };
                     send_message(&mut write, &message).await?;
                 } else {
                     println!("Usage: chat <message>");
                 }
             }
             "resign" => {
-                let message = ClientMessage::Resign;
+                let message = ClientEvent::Resign;
                 send_message(&mut write, &message).await?;
                 println!("Resigned from current game");
             }
+            "draw" => {
+                let message = match parts.get(1).copied() {
+                    None | Some("offer") => ClientEvent::OfferDraw,
+                    Some("accept") => ClientEvent::AcceptDraw,
+                    Some("decline") => ClientEvent::DeclineDraw,
+                    Some("claim") => ClientEvent::ClaimDraw,
+                    Some(_) => {
+                        println!("Usage: draw [offer|accept|decline|claim]");
+                        continue;
+                    }
+                };
+                send_message(&mut write, &message).await?;
+            }
+            "takeback" => {
+                let message = match parts.get(1).copied() {
+                    None | Some("request") => ClientEvent::RequestTakeback,
+                    Some("accept") => ClientEvent::AcceptTakeback,
+                    Some("decline") => ClientEvent::DeclineTakeback,
+                    Some(_) => {
+                        println!("Usage: takeback [request|accept|decline]");
+                        continue;
+                    }
+                };
+                send_message(&mut write, &message).await?;
+            }
             "help" => {
                 print_help();
             }
             "requestmoves" => {
                 if parts.len() >= 2 {
                     let fen = parts[1..].join(" ");
-                    let message = ClientMessage::RequestLegalMoves { fen };
+                    let message = ClientEvent::RequestLegalMoves { fen };
                     send_message(&mut write, &message).await?;
                 }
             }
@@ -207,7 +228,7 @@ async fn send_message(
         >,
         Message,
     >,
-    message: &ClientMessage,
+    message: &ClientEvent,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let json = serde_json::to_string(message)?;
     write.send(Message::Text(json)).await?;
@@ -217,12 +238,24 @@ async fn send_message(
 fn print_help() {
     println!("\n📖 Available Commands:");
     println!("  join <username>    - Register with a username");
-    println!("  findmatch          - Enter matchmaking queue");
+    println!("  rejoin <token> <username> - Reattach to the session of the token");
+    println!("  findmatch [m+s]    - Enter matchmaking queue, optionally with minutes+increment");
     println!("  move <from> <to>   - Make a chess move");
     println!("  chat <message>     - Send chat to opponent");
     println!("  resign             - Resign from current game");
+    println!("  draw [offer|accept|decline|claim] - Offer, answer or claim a draw");
+    println!("  takeback [request|accept|decline] - Ask for or answer a takeback");
     println!("  help               - Show this help");
     println!("  quit               - Exit the client");
     println!("  requestmoves       - Request the legal moves");
     println!();
 }
+
+// "5+3" is five minutes with a three second increment
+fn parse_time_control(text: &str) -> Option<TimeControl> {
+    let (minutes, increment) = text.split_once('+')?;
+    Some(TimeControl::Fischer {
+        base_ms: minutes.parse::<u64>().ok()? * 60_000,
+        increment_ms: increment.parse::<u64>().ok()? * 1_000,
+    })
+}
@@ -0,0 +1,181 @@
+use crate::servererror::ServerError;
+use std::collections::{HashSet, VecDeque};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+const MAX_CHAT_LENGTH: usize = 200;
+const RATE_LIMIT_MESSAGES: usize = 5;
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(10);
+
+// the chat messages a connection sent lately, it may send RATE_LIMIT_MESSAGES per RATE_LIMIT_WINDOW
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    sent: VecDeque<Instant>,
+}
+
+impl RateLimiter {
+    pub fn try_send(&mut self, now: Instant) -> bool {
+        while self
+            .sent
+            .front()
+            .is_some_and(|sent| now.saturating_duration_since(*sent) >= RATE_LIMIT_WINDOW)
+        {
+            self.sent.pop_front();
+        }
+        if self.sent.len() >= RATE_LIMIT_MESSAGES {
+            return false;
+        }
+        self.sent.push_back(now);
+        true
+    }
+}
+
+// moderation of the chat, filtered words are starred out and muted players cannot chat at all,
+// mutes go by the username of the session, which cannot be changed after the first Join
+#[derive(Debug)]
+pub struct ChatFilter {
+    words: HashSet<String>,
+    muted: HashSet<String>,
+}
+
+impl ChatFilter {
+    pub fn new(words: &[impl AsRef<str>], muted: &[impl AsRef<str>]) -> Self {
+        Self {
+            words: words
+                .iter()
+                .map(|word| word.as_ref().to_lowercase())
+                .collect(),
+            muted: muted
+                .iter()
+                .map(|username| username.as_ref().to_string())
+                .collect(),
+        }
+    }
+
+    // CHAT_FILTERED_WORDS and CHAT_MUTED_PLAYERS hold comma separated lists
+    fn from_env() -> Self {
+        let list = |name: &str| -> Vec<String> {
+            std::env::var(name)
+                .unwrap_or_default()
+                .split(',')
+                .map(|item| item.trim().to_string())
+                .filter(|item| !item.is_empty())
+                .collect()
+        };
+        Self::new(&list("CHAT_FILTERED_WORDS"), &list("CHAT_MUTED_PLAYERS"))
+    }
+
+    pub fn is_muted(&self, username: &str) -> bool {
+        self.muted.contains(username)
+    }
+
+    pub fn censor(&self, text: &str) -> String {
+        text.split(' ')
+            .map(|word| {
+                let bare = word.trim_matches(|c: char| !c.is_alphanumeric());
+                if !bare.is_empty() && self.words.contains(&bare.to_lowercase()) {
+                    word.replace(bare, &"*".repeat(bare.chars().count()))
+                } else {
+                    word.to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    // the text as it is relayed to the match
+    pub fn check_message(
+        &self,
+        username: &str,
+        text: &str,
+        limiter: &mut RateLimiter,
+        now: Instant,
+    ) -> Result<String, ServerError> {
+        if self.is_muted(username) {
+            return Err(ServerError::Muted);
+        }
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(ServerError::InvalidChatMessage(
+                "the message is empty".to_string(),
+            ));
+        }
+        if text.chars().count() > MAX_CHAT_LENGTH {
+            return Err(ServerError::InvalidChatMessage(format!(
+                "the message is longer than {} characters",
+                MAX_CHAT_LENGTH
+            )));
+        }
+        if !limiter.try_send(now) {
+            return Err(ServerError::RateLimited);
+        }
+        Ok(self.censor(text))
+    }
+}
+
+pub fn chat_filter() -> &'static ChatFilter {
+    static FILTER: OnceLock<ChatFilter> = OnceLock::new();
+    FILTER.get_or_init(ChatFilter::from_env)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limit() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::default();
+
+        for i in 0..RATE_LIMIT_MESSAGES {
+            assert!(limiter.try_send(start + Duration::from_secs(i as u64)));
+        }
+        assert!(!limiter.try_send(start + Duration::from_secs(9)));
+        // the first message left the window
+        assert!(limiter.try_send(start + RATE_LIMIT_WINDOW));
+        assert!(!limiter.try_send(start + RATE_LIMIT_WINDOW));
+    }
+
+    #[test]
+    fn test_censor() {
+        let filter = ChatFilter::new(&["Darn", "heck"], &["troll"]);
+
+        assert_eq!(filter.censor("darn it, HECK!"), "**** it, ****!");
+        assert_eq!(
+            filter.censor("heckle the darnedest"),
+            "heckle the darnedest"
+        );
+        assert_eq!(filter.censor("good  game"), "good  game");
+    }
+
+    #[test]
+    fn test_check_message() {
+        let filter = ChatFilter::new(&["heck"], &["troll"]);
+        let mut limiter = RateLimiter::default();
+        let now = Instant::now();
+
+        assert_eq!(
+            filter.check_message("troll", "hi", &mut limiter, now),
+            Err(ServerError::Muted)
+        );
+        assert!(matches!(
+            filter.check_message("player", "   ", &mut limiter, now),
+            Err(ServerError::InvalidChatMessage(_))
+        ));
+        assert!(matches!(
+            filter.check_message(
+                "player",
+                &"a".repeat(MAX_CHAT_LENGTH + 1),
+                &mut limiter,
+                now
+            ),
+            Err(ServerError::InvalidChatMessage(_))
+        ));
+        assert_eq!(
+            filter.check_message("player", " what the heck ", &mut limiter, now),
+            Ok("what the ****".to_string())
+        );
+        // rejected messages do not count towards the limit
+        assert_eq!(limiter.sent.len(), 1);
+    }
+}
@@ -0,0 +1,203 @@
+use protocol::{ClockTimes, TimeControl};
+use std::time::{Duration, Instant};
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+// chess clock of a match, only the side to move has a running clock
+#[derive(Debug, Clone)]
+pub struct Clock {
+    time_control: TimeControl,
+    // time left for white and black when their last turn started
+    remaining: [Duration; 2],
+    white_to_move: bool,
+    turn_started: Instant,
+}
+
+impl Clock {
+    // white's clock starts running right away
+    pub fn new(time_control: TimeControl, now: Instant) -> Self {
+        let base = match time_control {
+            TimeControl::Unlimited => Duration::ZERO,
+            TimeControl::Fischer { base_ms, .. }
+            | TimeControl::Bronstein { base_ms, .. }
+            | TimeControl::SimpleDelay { base_ms, .. } => Duration::from_millis(base_ms),
+            TimeControl::Correspondence { days_per_move } => DAY * days_per_move,
+        };
+
+        Self {
+            time_control,
+            remaining: [base, base],
+            white_to_move: true,
+            turn_started: now,
+        }
+    }
+
+    fn side(white: bool) -> usize {
+        if white { 0 } else { 1 }
+    }
+
+    pub fn remaining(&self, white: bool, now: Instant) -> Duration {
+        let remaining = self.remaining[Self::side(white)];
+        if white != self.white_to_move {
+            return remaining;
+        }
+
+        let used = now.saturating_duration_since(self.turn_started);
+        match self.time_control {
+            TimeControl::SimpleDelay { delay_ms, .. } => {
+                remaining.saturating_sub(used.saturating_sub(Duration::from_millis(delay_ms)))
+            }
+            _ => remaining.saturating_sub(used),
+        }
+    }
+
+    // whether the side to move has run out of time
+    pub fn is_flagged(&self, now: Instant) -> bool {
+        self.time_control != TimeControl::Unlimited
+            && self.remaining(self.white_to_move, now).is_zero()
+    }
+
+    // stops the clock of the side that just moved and starts the other one
+    pub fn punch(&mut self, now: Instant) {
+        let side = Self::side(self.white_to_move);
+        let used = now.saturating_duration_since(self.turn_started);
+        let left = self.remaining(self.white_to_move, now);
+
+        self.remaining[side] = match self.time_control {
+            TimeControl::Unlimited => Duration::ZERO,
+            TimeControl::Fischer { increment_ms, .. } => left + Duration::from_millis(increment_ms),
+            TimeControl::Bronstein { delay_ms, .. } => {
+                left + used.min(Duration::from_millis(delay_ms))
+            }
+            TimeControl::SimpleDelay { .. } => left,
+            TimeControl::Correspondence { days_per_move } => DAY * days_per_move,
+        };
+        self.white_to_move = !self.white_to_move;
+        self.turn_started = now;
+    }
+
+    // after a takeback the side to move again continues with the time it had left,
+    // the time the current turn took is charged as usual
+    pub fn take_back(&mut self, plies: usize, now: Instant) {
+        let side = Self::side(self.white_to_move);
+        self.remaining[side] = self.remaining(self.white_to_move, now);
+        if plies % 2 == 1 {
+            self.white_to_move = !self.white_to_move;
+        }
+        self.turn_started = now;
+    }
+
+    // None in games without a time control
+    pub fn times(&self, now: Instant) -> Option<ClockTimes> {
+        if self.time_control == TimeControl::Unlimited {
+            return None;
+        }
+        Some(ClockTimes {
+            white_ms: self.remaining(true, now).as_millis() as u64,
+            black_ms: self.remaining(false, now).as_millis() as u64,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn test_unlimited_clock_never_flags() {
+        let start = Instant::now();
+        let clock = Clock::new(TimeControl::Unlimited, start);
+
+        assert!(!clock.is_flagged(start + DAY * 365));
+        assert!(clock.times(start).is_none());
+    }
+
+    #[test]
+    fn test_fischer_increment() {
+        let start = Instant::now();
+        let mut clock = Clock::new(
+            TimeControl::Fischer {
+                base_ms: 60_000,
+                increment_ms: 2_000,
+            },
+            start,
+        );
+
+        clock.punch(start + secs(10));
+        assert_eq!(clock.remaining(true, start + secs(10)), secs(52));
+        assert_eq!(clock.remaining(false, start + secs(15)), secs(55));
+
+        let times = clock.times(start + secs(15)).unwrap();
+        assert_eq!(times.white_ms, 52_000);
+        assert_eq!(times.black_ms, 55_000);
+
+        assert!(!clock.is_flagged(start + secs(69)));
+        assert!(clock.is_flagged(start + secs(70)));
+    }
+
+    #[test]
+    fn test_bronstein_and_simple_delay() {
+        let start = Instant::now();
+        let bronstein = TimeControl::Bronstein {
+            base_ms: 60_000,
+            delay_ms: 5_000,
+        };
+        let simple_delay = TimeControl::SimpleDelay {
+            base_ms: 60_000,
+            delay_ms: 5_000,
+        };
+
+        // a quick move gets back everything it used, a slow one only the delay
+        let mut clock = Clock::new(bronstein, start);
+        assert_eq!(clock.remaining(true, start + secs(3)), secs(57));
+        clock.punch(start + secs(3));
+        assert_eq!(clock.remaining(true, start + secs(3)), secs(60));
+        clock.punch(start + secs(4));
+        clock.punch(start + secs(24));
+        assert_eq!(clock.remaining(true, start + secs(24)), secs(45));
+
+        // the delay passes before the clock starts to run, and counts towards the flag
+        let clock = Clock::new(simple_delay, start);
+        assert_eq!(clock.remaining(true, start + secs(3)), secs(60));
+        assert_eq!(clock.remaining(true, start + secs(15)), secs(50));
+        assert!(!clock.is_flagged(start + secs(64)));
+        assert!(clock.is_flagged(start + secs(65)));
+        assert!(Clock::new(bronstein, start).is_flagged(start + secs(60)));
+    }
+
+    #[test]
+    fn test_take_back() {
+        let start = Instant::now();
+        let mut clock = Clock::new(
+            TimeControl::Fischer {
+                base_ms: 60_000,
+                increment_ms: 0,
+            },
+            start,
+        );
+
+        clock.punch(start + secs(10));
+        clock.take_back(1, start + secs(15));
+        assert_eq!(clock.remaining(false, start + secs(15)), secs(55));
+        assert_eq!(clock.remaining(true, start + secs(20)), secs(45));
+
+        clock.take_back(2, start + secs(20));
+        assert_eq!(clock.remaining(true, start + secs(25)), secs(40));
+        assert_eq!(clock.remaining(false, start + secs(25)), secs(55));
+    }
+
+    #[test]
+    fn test_correspondence_resets_every_move() {
+        let start = Instant::now();
+        let mut clock = Clock::new(TimeControl::Correspondence { days_per_move: 3 }, start);
+
+        clock.punch(start + DAY * 2);
+        assert_eq!(clock.remaining(true, start + DAY * 2), DAY * 3);
+        assert!(!clock.is_flagged(start + DAY * 5 - secs(1)));
+        assert!(clock.is_flagged(start + DAY * 5));
+    }
+}
@@ -1,13 +1,17 @@
-use crate::connection::ClientEvent::*;
+use crate::chat::{self, RateLimiter};
+use crate::clock::Clock;
 use crate::matchmaking;
+use crate::servererror::ServerError;
 use engine::chessmove::ChessMove;
 use engine::gameend::GameEnd::{self, *};
-use engine::{get_available_moves, is_game_over};
+use engine::get_available_moves;
 use futures_util::{SinkExt, StreamExt};
-use log::{error, info, warn};
-use serde::{Deserialize, Serialize};
+use log::{debug, error, info, warn};
+use protocol::ClientEvent::{self, *};
+use protocol::{Offer, PROTOCOL_VERSION, ServerMessage2, TimeControl};
 use std::collections::{HashMap, VecDeque};
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::net::TcpStream;
 use tokio::sync::Mutex;
 use tokio_tungstenite::{WebSocketStream, tungstenite::Message};
@@ -18,6 +22,23 @@ pub type Tx = futures_util::stream::SplitSink<WebSocketStream<TcpStream>, Messag
 pub type ConnectionMap = Arc<Mutex<HashMap<Uuid, PlayerConnection>>>;
 pub type MatchMap = Arc<Mutex<HashMap<Uuid, GameMatch>>>;
 pub type WaitingQueue = Arc<Mutex<VecDeque<Uuid>>>;
+// session token -> session, issued at Join so a dropped player can get back into its match
+pub type SessionMap = Arc<Mutex<HashMap<Uuid, Session>>>;
+
+const DEFAULT_RECONNECT_WINDOW: Duration = Duration::from_secs(60);
+
+// how long a player who dropped out of a match has to come back,
+// RECONNECT_TIMEOUT_SECS overrides the default
+pub fn reconnect_window() -> Duration {
+    static WINDOW: OnceLock<Duration> = OnceLock::new();
+    *WINDOW.get_or_init(|| {
+        std::env::var("RECONNECT_TIMEOUT_SECS")
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_RECONNECT_WINDOW)
+    })
+}
 
 pub async fn clean_up_match(matches: &MatchMap, match_id: &Uuid) {
     matches.lock().await.remove(&match_id);
@@ -39,48 +60,25 @@ pub fn new_waiting_queue() -> WaitingQueue {
     Arc::new(Mutex::new(VecDeque::new()))
 }
 
-#[derive(Serialize, Deserialize)]
-pub enum ServerMessage2 {
-    GameEnd {
-        winner: GameEnd,
-    },
-    UIUpdate {
-        fen: String,
-        turn_player: String,
-        move_history: Vec<String>,
-    },
-    MatchFound {
-        match_id: Uuid,
-        color: String,
-        opponent_name: String,
-    },
-    LegalMoves {
-        moves: Vec<ChessMove>,
-    },
-    Ok {
-        response: Result<(), String>,
-    },
+pub fn new_session_map() -> SessionMap {
+    warn!("Created new session map");
+    Arc::new(Mutex::new(HashMap::new()))
 }
 
-#[derive(Serialize, Deserialize)]
-#[serde(tag = "type")]
-enum ClientEvent {
-    Join {
-        username: String,
-    },
-    FindMatch,
-    Move {
-        step: ChessMove,
-        turn_player: String,
-    },
-    Resign,
-    Chat {
-        text: String,
-    },
-    RequestLegalMoves {
-        fen: String,
-    },
-    CloseConnection,
+impl From<ServerError> for ServerMessage2 {
+    fn from(e: ServerError) -> Self {
+        ServerMessage2::Error {
+            code: e.code(),
+            message: e.to_string(),
+        }
+    }
+}
+
+// the username is fixed for the whole session, chat mutes and the opponent name rely on it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub player_id: Uuid,
+    pub username: String,
 }
 
 #[derive(Debug)]
@@ -89,6 +87,9 @@ pub struct PlayerConnection {
     pub username: Option<String>,
     pub tx: Tx,
     pub current_match: Option<Uuid>,
+    // the time control asked for in the last FindMatch
+    pub time_control: TimeControl,
+    pub chat_limiter: RateLimiter,
 }
 
 #[derive(Debug, Clone)]
@@ -98,6 +99,157 @@ pub struct GameMatch {
     pub player_black: Uuid,
     pub board_state: String,
     pub move_history: Vec<String>,
+    pub position_history: Vec<String>,
+    pub clock: Clock,
+    // players who dropped out and the deadline for coming back
+    pub disconnects: HashMap<Uuid, Instant>,
+    pub pending_offer: Option<PendingOffer>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PendingOffer {
+    pub offer: Offer,
+    pub from: Uuid,
+}
+
+impl GameMatch {
+    // the side to move comes from the board state, it is never taken from the client
+    pub fn is_white_to_move(&self) -> bool {
+        self.board_state.split_whitespace().nth(1) != Some("b")
+    }
+
+    pub fn turn_player(&self) -> String {
+        if self.is_white_to_move() {
+            String::from("white")
+        } else {
+            String::from("black")
+        }
+    }
+
+    pub fn player_to_move(&self) -> Uuid {
+        if self.is_white_to_move() {
+            self.player_white
+        } else {
+            self.player_black
+        }
+    }
+
+    pub fn color_of(&self, player_id: &Uuid) -> String {
+        if *player_id == self.player_white {
+            String::from("white")
+        } else {
+            String::from("black")
+        }
+    }
+
+    pub fn opponent_of(&self, player_id: &Uuid) -> Option<Uuid> {
+        if *player_id == self.player_white {
+            Some(self.player_black)
+        } else if *player_id == self.player_black {
+            Some(self.player_white)
+        } else {
+            None
+        }
+    }
+
+    pub fn ui_update(&self, now: Instant) -> ServerMessage2 {
+        ServerMessage2::UIUpdate {
+            fen: self.board_state.clone(),
+            turn_player: self.turn_player(),
+            move_history: self.move_history.clone(),
+            clock: self.clock.times(now),
+        }
+    }
+
+    // the player whose reconnection window ran out loses, a draw if neither came back
+    pub fn abandon_result(&self, now: Instant) -> Option<GameEnd> {
+        let gone = |player_id: &Uuid| {
+            self.disconnects
+                .get(player_id)
+                .is_some_and(|deadline| *deadline <= now)
+        };
+        match (gone(&self.player_white), gone(&self.player_black)) {
+            (true, true) => Some(GameEnd::Draw("Abandoned".to_string())),
+            (true, false) => Some(GameEnd::BlackWon("Abandoned".to_string())),
+            (false, true) => Some(GameEnd::WhiteWon("Abandoned".to_string())),
+            (false, false) => None,
+        }
+    }
+
+    // the player to move lost on time, unless the opponent could never have mated
+    pub fn time_out_result(&self) -> GameEnd {
+        let white_flagged = self.is_white_to_move();
+        match engine::has_mating_material(&self.board_state, !white_flagged) {
+            Ok(false) => GameEnd::Draw("Timeout vs insufficient material".to_string()),
+            _ if white_flagged => GameEnd::BlackWon("Time".to_string()),
+            _ => GameEnd::WhiteWon("Time".to_string()),
+        }
+    }
+
+    // only one offer can wait for an answer at a time
+    pub fn make_offer(&mut self, player_id: Uuid, offer: Offer) -> Result<(), ServerError> {
+        self.opponent_of(&player_id)
+            .ok_or(ServerError::NotAPlayer)?;
+        if self.pending_offer.is_some() {
+            return Err(ServerError::OfferPending);
+        }
+        if offer == Offer::Takeback && self.takeback_plies(&player_id) > self.position_history.len()
+        {
+            return Err(ServerError::NothingToTakeBack);
+        }
+        self.pending_offer = Some(PendingOffer {
+            offer,
+            from: player_id,
+        });
+        Ok(())
+    }
+
+    // takes the offer off the table, only the opponent of the one who made it can answer
+    pub fn answer_offer(
+        &mut self,
+        player_id: &Uuid,
+        offer: Offer,
+    ) -> Result<PendingOffer, ServerError> {
+        self.opponent_of(player_id).ok_or(ServerError::NotAPlayer)?;
+        match self.pending_offer {
+            Some(pending) if pending.offer == offer && pending.from != *player_id => {
+                self.pending_offer = None;
+                Ok(pending)
+            }
+            _ => Err(ServerError::NoPendingOffer),
+        }
+    }
+
+    // plies to undo so that the player asking for the takeback is to move again
+    pub fn takeback_plies(&self, player_id: &Uuid) -> usize {
+        if *player_id == self.player_to_move() {
+            2
+        } else {
+            1
+        }
+    }
+
+    pub fn take_back(&mut self, plies: usize, now: Instant) -> Result<(), ServerError> {
+        if plies > self.position_history.len() {
+            return Err(ServerError::NothingToTakeBack);
+        }
+        for _ in 0..plies {
+            self.board_state = self.position_history.pop().unwrap();
+            self.move_history.pop();
+        }
+        self.clock.take_back(plies, now);
+        Ok(())
+    }
+
+    pub fn check_turn(&self, player_id: &Uuid) -> Result<(), ServerError> {
+        if *player_id != self.player_white && *player_id != self.player_black {
+            return Err(ServerError::NotAPlayer);
+        }
+        if *player_id != self.player_to_move() {
+            return Err(ServerError::NotYourTurn);
+        }
+        Ok(())
+    }
 }
 
 // Message sending utilities
@@ -143,16 +295,19 @@ pub async fn broadcast_to_match(
     info!("Broadcasting data to match: {}", &match_id);
     let matches_lock = matches.lock().await;
     if let Some(game_match) = matches_lock.get(&match_id) {
-        send_message_to_player_connection(
+        // a disconnected player must not keep the message from the other one
+        let white_result = send_message_to_player_connection(
             connections.lock().await.get_mut(&game_match.player_white),
             message,
         )
-        .await?;
-        send_message_to_player_connection(
+        .await;
+        let black_result = send_message_to_player_connection(
             connections.lock().await.get_mut(&game_match.player_black),
             message,
         )
-        .await?;
+        .await;
+        white_result?;
+        black_result?;
     }
     Ok(())
 }
@@ -163,6 +318,7 @@ pub async fn handle_connection(
     connections: ConnectionMap,
     matches: MatchMap,
     waiting_queue: WaitingQueue,
+    sessions: SessionMap,
 ) -> anyhow::Result<()> {
     use tokio_tungstenite::accept_async;
 
@@ -170,7 +326,8 @@ pub async fn handle_connection(
     let (write, mut read) = ws_stream.split();
     warn!("Accepted new connection");
 
-    let player_id = Uuid::new_v4();
+    // replaced by the id of an earlier session when the player reattaches in Join
+    let mut player_id = Uuid::new_v4();
 
     // Store the connection
     {
@@ -182,224 +339,640 @@ pub async fn handle_connection(
                 username: None,
                 tx: write,
                 current_match: None,
+                time_control: TimeControl::default(),
+                chat_limiter: RateLimiter::default(),
             },
         );
     }
 
     info!("id: {}", &player_id);
 
-    println!("\n\n\n");
-    println!(
-        "{:?}",
-        engine::get_available_moves("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")
-    );
-    println!("\n\n\n");
-
     // Message processing loop
     while let Some(Ok(message)) = read.next().await {
         if message.is_text() {
             let text = message.to_text()?;
             info!("Received from {}: {}", player_id, text);
 
-            let client_data: ClientEvent = serde_json::from_str(text)
-                .expect("Failed to convert data into json at handle_connection");
-
-            match client_data {
-                Join { username } => {
-                    {
-                        let mut conn_map = connections.lock().await;
-                        let player = conn_map.get_mut(&player_id).unwrap();
-                        player.username = Some(username.clone());
-                        info!("player: {}, set username: {}", &player_id, username);
-                    }
-
-                    //respone to client
-                    let response = ServerMessage2::Ok { response: Ok(()) };
-
-                    let mut conn_map = connections.lock().await;
-                    let _ = send_message_to_player_connection(
-                        conn_map.get_mut(&player_id),
-                        &serde_json::to_string(&response).unwrap(),
-                    )
-                    .await;
-                }
-                FindMatch => {
-                    let mut wait_queue = waiting_queue.lock().await;
-                    wait_queue.push_back(player_id.clone());
-                    info!("Appended {} to the waiting queue", player_id);
-                    info!("queue {:?}", wait_queue);
-                }
-                Move { step, turn_player } => {
-                    let match_id = connections
-                        .lock()
-                        .await
-                        .get(&player_id)
-                        .unwrap()
-                        .current_match
-                        .unwrap();
-
-                    println!("\n\nstep: {:?}\n", step);
-
-                    {
-                        info!("updating board state in match: {}", &match_id);
-                        let mut matches = matches.lock().await;
-                        matches.get_mut(&match_id).unwrap().board_state =
-                            engine::get_board_after_move(
-                                &matches.get(&match_id).unwrap().board_state,
-                                &step,
-                            );
-
-                        info!(
-                            "board after engine fn: {}",
-                            matches.get_mut(&match_id).unwrap().board_state.clone()
-                        );
-
-                        matches
-                            .get_mut(&match_id)
-                            .unwrap()
-                            .move_history
-                            .push(step.clone().notation());
-                    }
-
-                    let message = ServerMessage2::UIUpdate {
-                        fen: {
-                            let mut matches = matches.lock().await;
-                            matches.get(&match_id).unwrap().board_state.clone()
-                        },
-                        turn_player: turn_player,
-                        move_history: {
-                            let mut matches = matches.lock().await;
-                            matches.get(&match_id).unwrap().move_history.clone()
-                        },
-                    };
-
-                    let _ = broadcast_to_match(
-                        &connections,
-                        &matches,
-                        match_id,
-                        &serde_json::to_string(&message).unwrap(),
-                    )
-                    .await;
-
-                    {
-                        let is_game_end = engine::is_game_over(
-                            &matches.lock().await.get(&match_id).unwrap().board_state,
-                        );
-
-                        match is_game_end {
-                            Some(res) => {
-                                warn!("A player won the match: {}", &match_id);
-                                let message = ServerMessage2::GameEnd { winner: res };
-                                let _ = broadcast_to_match(
-                                    &connections,
-                                    &matches,
-                                    match_id,
-                                    &serde_json::to_string(&message).unwrap(),
-                                )
-                                .await;
-                                clean_up_match(&matches, &match_id).await;
-                            }
-                            None => {
-                                info!("No winner match continues. Id: {}", &match_id);
-                            }
-                        }
-                    }
-                }
-                RequestLegalMoves { fen } => {
-                    info!("Requesting legal moves player: {}", &player_id);
-                    let moves = get_available_moves(&fen);
-                    let message = ServerMessage2::LegalMoves { moves };
-                    let _ = send_message_to_player_connection(
-                        connections.lock().await.get_mut(&player_id),
-                        &serde_json::to_string(&message).unwrap(),
-                    )
-                    .await;
-                    info!("Sent moves to player: {}", player_id);
+            let result = match parse_client_event(text) {
+                Ok(CloseConnection) => {
+                    warn!("Closing connection for: {}", &player_id);
+                    break;
                 }
-                Resign => {
-                    warn!("Resigned!");
-                    let (fuck, fuck_id): (ServerMessage2, &Uuid) = {
-                        let matches = matches.lock().await;
-
-                        let curr_match = matches
-                            .get(
-                                &connections
-                                    .lock()
-                                    .await
-                                    .get(&player_id)
-                                    .unwrap()
-                                    .current_match
-                                    .unwrap(),
-                            )
-                            .unwrap();
-
-                        if player_id == curr_match.player_white {
-                            (
-                                ServerMessage2::GameEnd {
-                                    winner: GameEnd::BlackWon("Resigned".to_string()),
-                                },
-                                &connections
-                                    .lock()
-                                    .await
-                                    .get(&player_id)
-                                    .unwrap()
-                                    .current_match
-                                    .unwrap(),
-                            )
-                        } else {
-                            (
-                                ServerMessage2::GameEnd {
-                                    winner: GameEnd::WhiteWon("Resigned".to_string()),
-                                },
-                                &connections
-                                    .lock()
-                                    .await
-                                    .get(&player_id)
-                                    .unwrap()
-                                    .current_match
-                                    .unwrap(),
-                            )
-                        }
-                    };
-
-                    let _ = broadcast_to_match(
+                Ok(client_data) => {
+                    handle_event(
+                        &mut player_id,
+                        client_data,
                         &connections,
                         &matches,
-                        fuck_id.clone(),
-                        &serde_json::to_string(&fuck).unwrap(),
+                        &waiting_queue,
+                        &sessions,
                     )
-                    .await;
-                    clean_up_match(&matches, fuck_id).await;
-                }
-                CloseConnection => {
-                    warn!("Closing connection for: {}", &player_id);
-                    break;
-                }
-                _ => {
-                    warn!("Not known client event");
+                    .await
                 }
+                Err(e) => Err(e),
+            };
+
+            // errors are reported to the sender, the connection stays open
+            if let Err(e) = result {
+                warn!("Error for player {}: {}", &player_id, e);
+                let message = ServerMessage2::from(e);
+                let _ = send_message_to_player_connection(
+                    connections.lock().await.get_mut(&player_id),
+                    &serde_json::to_string(&message).unwrap(),
+                )
+                .await;
             }
         }
     }
 
     // Cleanup on disconnect
-    cleanup_player(player_id, &connections, &matches, &waiting_queue).await;
+    cleanup_player(player_id, &connections, &matches, &waiting_queue, &sessions).await;
     warn!("Connection {} closed", player_id);
 
     Ok(())
 }
 
+fn parse_client_event(text: &str) -> Result<ClientEvent, ServerError> {
+    serde_json::from_str(text).map_err(|e| ServerError::MalformedMessage(e.to_string()))
+}
+
+async fn current_match_id(
+    player_id: Uuid,
+    connections: &ConnectionMap,
+) -> Result<Uuid, ServerError> {
+    connections
+        .lock()
+        .await
+        .get(&player_id)
+        .and_then(|player| player.current_match)
+        .ok_or(ServerError::NotInMatch)
+}
+
+async fn handle_event(
+    player_id: &mut Uuid,
+    client_data: ClientEvent,
+    connections: &ConnectionMap,
+    matches: &MatchMap,
+    waiting_queue: &WaitingQueue,
+    sessions: &SessionMap,
+) -> Result<(), ServerError> {
+    if let Join {
+        username,
+        protocol_version,
+        session_token,
+    } = client_data
+    {
+        if protocol_version != PROTOCOL_VERSION {
+            return Err(ServerError::IncompatibleVersion(protocol_version));
+        }
+        return handle_join(
+            player_id,
+            username,
+            session_token,
+            connections,
+            matches,
+            sessions,
+        )
+        .await;
+    }
+
+    let player_id = *player_id;
+    match client_data {
+        FindMatch { time_control } => {
+            if let Some(player) = connections.lock().await.get_mut(&player_id) {
+                player.time_control = time_control;
+            }
+            let mut wait_queue = waiting_queue.lock().await;
+            wait_queue.push_back(player_id);
+            info!("Appended {} to the waiting queue", player_id);
+            info!("queue {:?}", wait_queue);
+        }
+        Move { step, .. } => {
+            handle_move(player_id, step, connections, matches).await?;
+        }
+        RequestLegalMoves { fen } => {
+            info!("Requesting legal moves player: {}", &player_id);
+            let moves = get_available_moves(&fen)?;
+            let message = ServerMessage2::LegalMoves { moves };
+            let _ = send_message_to_player_connection(
+                connections.lock().await.get_mut(&player_id),
+                &serde_json::to_string(&message).unwrap(),
+            )
+            .await;
+            info!("Sent moves to player: {}", player_id);
+        }
+        Resign => {
+            handle_resign(player_id, connections, matches).await?;
+        }
+        Chat { text } => {
+            handle_chat(player_id, text, connections, matches).await?;
+        }
+        OfferDraw => {
+            handle_offer(player_id, Offer::Draw, connections, matches).await?;
+        }
+        AcceptDraw => {
+            handle_offer_answer(player_id, Offer::Draw, true, connections, matches).await?;
+        }
+        DeclineDraw => {
+            handle_offer_answer(player_id, Offer::Draw, false, connections, matches).await?;
+        }
+        RequestTakeback => {
+            handle_offer(player_id, Offer::Takeback, connections, matches).await?;
+        }
+        AcceptTakeback => {
+            handle_offer_answer(player_id, Offer::Takeback, true, connections, matches).await?;
+        }
+        DeclineTakeback => {
+            handle_offer_answer(player_id, Offer::Takeback, false, connections, matches).await?;
+        }
+        ClaimDraw => {
+            handle_claim_draw(player_id, connections, matches).await?;
+        }
+        Unknown => {
+            return Err(ServerError::UnknownEvent);
+        }
+        _ => {
+            warn!("Not known client event");
+        }
+    }
+    Ok(())
+}
+
+async fn handle_join(
+    player_id: &mut Uuid,
+    username: String,
+    session_token: Option<Uuid>,
+    connections: &ConnectionMap,
+    matches: &MatchMap,
+    sessions: &SessionMap,
+) -> Result<(), ServerError> {
+    let (token, username, reattached) = match session_token {
+        Some(token) => {
+            let session = sessions
+                .lock()
+                .await
+                .get(&token)
+                .cloned()
+                .ok_or(ServerError::InvalidSession)?;
+            let reattached = session.player_id != *player_id;
+            if reattached {
+                reattach(player_id, session.player_id, connections, matches).await?;
+            }
+            // the name of the session wins, a rejoin cannot rename the player
+            (token, session.username, reattached)
+        }
+        None => {
+            let already_joined = connections
+                .lock()
+                .await
+                .get(player_id)
+                .is_some_and(|player| player.username.is_some());
+            if already_joined {
+                return Err(ServerError::AlreadyJoined);
+            }
+
+            let mut sessions = sessions.lock().await;
+            if sessions
+                .values()
+                .any(|session| session.username == username)
+            {
+                return Err(ServerError::UsernameTaken);
+            }
+            let token = Uuid::new_v4();
+            sessions.insert(
+                token,
+                Session {
+                    player_id: *player_id,
+                    username: username.clone(),
+                },
+            );
+            (token, username, false)
+        }
+    };
+
+    {
+        let mut conn_map = connections.lock().await;
+        if let Some(player) = conn_map.get_mut(player_id) {
+            player.username = Some(username.clone());
+            info!("player: {}, set username: {}", &player_id, username);
+        }
+    }
+
+    //respone to client
+    for response in [
+        ServerMessage2::Session { token },
+        ServerMessage2::Ok { response: Ok(()) },
+    ] {
+        let _ = send_message_to_player_connection(
+            connections.lock().await.get_mut(player_id),
+            &serde_json::to_string(&response).unwrap(),
+        )
+        .await;
+    }
+
+    if reattached {
+        resend_match_state(*player_id, connections, matches).await;
+    }
+    Ok(())
+}
+
+// moves the new connection over to the player id of the session, which must not be connected anymore
+async fn reattach(
+    player_id: &mut Uuid,
+    previous_id: Uuid,
+    connections: &ConnectionMap,
+    matches: &MatchMap,
+) -> Result<(), ServerError> {
+    let current_match = matches
+        .lock()
+        .await
+        .values()
+        .find(|game_match| game_match.opponent_of(&previous_id).is_some())
+        .map(|game_match| game_match.id);
+
+    let mut conn_map = connections.lock().await;
+    if conn_map.contains_key(&previous_id) {
+        return Err(ServerError::InvalidSession);
+    }
+    let mut connection = conn_map
+        .remove(player_id)
+        .ok_or(ServerError::InvalidSession)?;
+    connection.id = previous_id;
+    connection.current_match = current_match;
+    conn_map.insert(previous_id, connection);
+
+    info!("player {} reattached as {}", &player_id, &previous_id);
+    *player_id = previous_id;
+    Ok(())
+}
+
+// everything a reattached player needs to continue its match
+async fn resend_match_state(player_id: Uuid, connections: &ConnectionMap, matches: &MatchMap) {
+    let Ok(match_id) = current_match_id(player_id, connections).await else {
+        return;
+    };
+
+    let (color, opponent, ui_update) = {
+        let mut matches = matches.lock().await;
+        let Some(game_match) = matches.get_mut(&match_id) else {
+            return;
+        };
+        game_match.disconnects.remove(&player_id);
+
+        let color = game_match.color_of(&player_id);
+        let opponent = game_match.opponent_of(&player_id).unwrap();
+        (color, opponent, game_match.ui_update(Instant::now()))
+    };
+
+    let mut conn_map = connections.lock().await;
+    let opponent_name = conn_map
+        .get(&opponent)
+        .and_then(|c| c.username.clone())
+        .unwrap_or_else(|| "Opponent".to_string());
+    let match_found = ServerMessage2::MatchFound {
+        match_id,
+        color,
+        opponent_name,
+    };
+
+    for message in [match_found, ui_update] {
+        let _ = send_message_to_player_connection(
+            conn_map.get_mut(&player_id),
+            &serde_json::to_string(&message).unwrap(),
+        )
+        .await;
+    }
+    let _ = send_message_to_player_connection(
+        conn_map.get_mut(&opponent),
+        &serde_json::to_string(&ServerMessage2::OpponentReconnected).unwrap(),
+    )
+    .await;
+}
+
+async fn handle_move(
+    player_id: Uuid,
+    step: ChessMove,
+    connections: &ConnectionMap,
+    matches: &MatchMap,
+) -> Result<(), ServerError> {
+    let match_id = current_match_id(player_id, connections).await?;
+
+    debug!("step from {}: {:?}", player_id, step);
+
+    let expired_offer;
+    {
+        info!("updating board state in match: {}", &match_id);
+        let mut matches = matches.lock().await;
+        let game_match = matches.get_mut(&match_id).ok_or(ServerError::NotInMatch)?;
+        game_match.check_turn(&player_id)?;
+
+        // the flag watcher ends the game, a move after the flag fell does not count
+        let now = Instant::now();
+        if game_match.clock.is_flagged(now) {
+            return Err(ServerError::OutOfTime);
+        }
+
+        let fen = engine::try_play(&game_match.board_state, &step)?;
+        let san = step
+            .to_san(&game_match.board_state)
+            .unwrap_or_else(|_| step.notation());
+        let previous_state = std::mem::replace(&mut game_match.board_state, fen);
+        game_match.position_history.push(previous_state);
+        game_match.move_history.push(san);
+        game_match.clock.punch(now);
+        // a move answers whatever was still on the table
+        expired_offer = game_match.pending_offer.take();
+
+        info!("board after engine fn: {}", game_match.board_state);
+    }
+
+    if let Some(pending) = expired_offer {
+        let message = ServerMessage2::OfferExpired {
+            offer: pending.offer,
+        };
+        let _ = broadcast_to_match(
+            connections,
+            matches,
+            match_id,
+            &serde_json::to_string(&message).unwrap(),
+        )
+        .await;
+    }
+
+    let message = {
+        let matches = matches.lock().await;
+        let game_match = matches.get(&match_id).ok_or(ServerError::NotInMatch)?;
+        game_match.ui_update(Instant::now())
+    };
+
+    let _ = broadcast_to_match(
+        connections,
+        matches,
+        match_id,
+        &serde_json::to_string(&message).unwrap(),
+    )
+    .await;
+
+    let is_game_end = {
+        let matches = matches.lock().await;
+        let game_match = matches.get(&match_id).ok_or(ServerError::NotInMatch)?;
+        engine::is_game_over_with_history(&game_match.board_state, &game_match.position_history)
+    };
+
+    match is_game_end {
+        Ok(Some(res)) => {
+            warn!("A player won the match: {}", &match_id);
+            let message = ServerMessage2::GameEnd { winner: res };
+            let _ = broadcast_to_match(
+                connections,
+                matches,
+                match_id,
+                &serde_json::to_string(&message).unwrap(),
+            )
+            .await;
+            clean_up_match(matches, &match_id).await;
+        }
+        Ok(None) => {
+            info!("No winner match continues. Id: {}", &match_id);
+        }
+        Err(e) => {
+            error!("Could not check game end in match {}: {}", &match_id, e);
+        }
+    }
+    Ok(())
+}
+
+async fn handle_resign(
+    player_id: Uuid,
+    connections: &ConnectionMap,
+    matches: &MatchMap,
+) -> Result<(), ServerError> {
+    warn!("Resigned!");
+    let match_id = current_match_id(player_id, connections).await?;
+
+    let message = {
+        let matches = matches.lock().await;
+        let game_match = matches.get(&match_id).ok_or(ServerError::NotInMatch)?;
+
+        if player_id == game_match.player_white {
+            ServerMessage2::GameEnd {
+                winner: GameEnd::BlackWon("Resigned".to_string()),
+            }
+        } else if player_id == game_match.player_black {
+            ServerMessage2::GameEnd {
+                winner: GameEnd::WhiteWon("Resigned".to_string()),
+            }
+        } else {
+            return Err(ServerError::NotAPlayer);
+        }
+    };
+
+    let _ = broadcast_to_match(
+        connections,
+        matches,
+        match_id,
+        &serde_json::to_string(&message).unwrap(),
+    )
+    .await;
+    clean_up_match(matches, &match_id).await;
+    Ok(())
+}
+
+async fn handle_chat(
+    player_id: Uuid,
+    text: String,
+    connections: &ConnectionMap,
+    matches: &MatchMap,
+) -> Result<(), ServerError> {
+    let match_id = current_match_id(player_id, connections).await?;
+
+    let message = {
+        let mut conn_map = connections.lock().await;
+        let player = conn_map
+            .get_mut(&player_id)
+            .ok_or(ServerError::NotInMatch)?;
+        // mutes go by the username, a connection without one could get around them
+        let from = player.username.clone().ok_or(ServerError::NotJoined)?;
+        let text = chat::chat_filter().check_message(
+            &from,
+            &text,
+            &mut player.chat_limiter,
+            Instant::now(),
+        )?;
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_millis() as u64)
+            .unwrap_or_default();
+        ServerMessage2::ChatMessage {
+            from,
+            text,
+            timestamp,
+        }
+    };
+
+    let _ = broadcast_to_match(
+        connections,
+        matches,
+        match_id,
+        &serde_json::to_string(&message).unwrap(),
+    )
+    .await;
+    Ok(())
+}
+
+async fn handle_offer(
+    player_id: Uuid,
+    offer: Offer,
+    connections: &ConnectionMap,
+    matches: &MatchMap,
+) -> Result<(), ServerError> {
+    let match_id = current_match_id(player_id, connections).await?;
+
+    let message = {
+        let mut matches = matches.lock().await;
+        let game_match = matches.get_mut(&match_id).ok_or(ServerError::NotInMatch)?;
+        game_match.make_offer(player_id, offer)?;
+        ServerMessage2::OfferMade {
+            offer,
+            color: game_match.color_of(&player_id),
+        }
+    };
+
+    info!("{:?} offered in match: {}", offer, &match_id);
+    let _ = broadcast_to_match(
+        connections,
+        matches,
+        match_id,
+        &serde_json::to_string(&message).unwrap(),
+    )
+    .await;
+    Ok(())
+}
+
+async fn handle_offer_answer(
+    player_id: Uuid,
+    offer: Offer,
+    accept: bool,
+    connections: &ConnectionMap,
+    matches: &MatchMap,
+) -> Result<(), ServerError> {
+    let match_id = current_match_id(player_id, connections).await?;
+
+    let message = {
+        let mut matches = matches.lock().await;
+        let game_match = matches.get_mut(&match_id).ok_or(ServerError::NotInMatch)?;
+        let pending = game_match.answer_offer(&player_id, offer)?;
+
+        match (offer, accept) {
+            (_, false) => ServerMessage2::OfferDeclined { offer },
+            (Offer::Draw, true) => ServerMessage2::GameEnd {
+                winner: GameEnd::Draw("Agreement".to_string()),
+            },
+            (Offer::Takeback, true) => {
+                let now = Instant::now();
+                let plies = game_match.takeback_plies(&pending.from);
+                game_match.take_back(plies, now)?;
+                game_match.ui_update(now)
+            }
+        }
+    };
+
+    let is_game_end = matches!(message, ServerMessage2::GameEnd { .. });
+    let _ = broadcast_to_match(
+        connections,
+        matches,
+        match_id,
+        &serde_json::to_string(&message).unwrap(),
+    )
+    .await;
+    if is_game_end {
+        warn!("Draw agreed in match: {}", &match_id);
+        clean_up_match(matches, &match_id).await;
+    }
+    Ok(())
+}
+
+async fn handle_claim_draw(
+    player_id: Uuid,
+    connections: &ConnectionMap,
+    matches: &MatchMap,
+) -> Result<(), ServerError> {
+    let match_id = current_match_id(player_id, connections).await?;
+
+    let winner = {
+        let matches = matches.lock().await;
+        let game_match = matches.get(&match_id).ok_or(ServerError::NotInMatch)?;
+        game_match
+            .opponent_of(&player_id)
+            .ok_or(ServerError::NotAPlayer)?;
+        engine::get_claimable_draw(&game_match.board_state, &game_match.position_history)?
+            .ok_or(ServerError::NoDrawToClaim)?
+    };
+
+    warn!("Draw claimed in match: {}", &match_id);
+    let message = ServerMessage2::GameEnd { winner };
+    let _ = broadcast_to_match(
+        connections,
+        matches,
+        match_id,
+        &serde_json::to_string(&message).unwrap(),
+    )
+    .await;
+    clean_up_match(matches, &match_id).await;
+    Ok(())
+}
+
 async fn cleanup_player(
     player_id: Uuid,
     connections: &ConnectionMap,
-    _matches: &MatchMap,
+    matches: &MatchMap,
     waiting_queue: &WaitingQueue,
+    sessions: &SessionMap,
 ) {
     // Remove from waiting queue
     waiting_queue.lock().await.retain(|&id| id != player_id);
 
     // Remove from connections
-    connections.lock().await.remove(&player_id);
+    let current_match = connections
+        .lock()
+        .await
+        .remove(&player_id)
+        .and_then(|player| player.current_match);
+
+    // a player who drops out of a running match keeps its session until the window runs out
+    let opponent = match current_match {
+        Some(match_id) => matches
+            .lock()
+            .await
+            .get_mut(&match_id)
+            .and_then(|game_match| {
+                let opponent = game_match.opponent_of(&player_id)?;
+                let deadline = Instant::now() + reconnect_window();
+                game_match.disconnects.insert(player_id, deadline);
+                Some(opponent)
+            }),
+        None => None,
+    };
+
+    match opponent {
+        Some(opponent) => {
+            warn!("Player {} dropped out of a match", player_id);
+            let message = ServerMessage2::OpponentDisconnected {
+                timeout_ms: reconnect_window().as_millis() as u64,
+            };
+            let _ = send_message_to_player_connection(
+                connections.lock().await.get_mut(&opponent),
+                &serde_json::to_string(&message).unwrap(),
+            )
+            .await;
+        }
+        None => {
+            sessions
+                .lock()
+                .await
+                .retain(|_, session| session.player_id != player_id);
+        }
+    }
 
     warn!("Cleaned up player {}", player_id);
 }
@@ -434,6 +1007,482 @@ mod tests {
         println!("Test passed: Handles non-existent player in map correctly");
     }
 
+    #[test]
+    fn test_turn_is_taken_from_board_state() {
+        let player_white = Uuid::new_v4();
+        let player_black = Uuid::new_v4();
+        let mut game_match = GameMatch {
+            id: Uuid::new_v4(),
+            player_white,
+            player_black,
+            board_state: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string(),
+            move_history: Vec::new(),
+            position_history: Vec::new(),
+            clock: Clock::new(TimeControl::Unlimited, Instant::now()),
+            disconnects: HashMap::new(),
+            pending_offer: None,
+        };
+
+        assert_eq!(game_match.turn_player(), "white");
+        assert!(game_match.check_turn(&player_white).is_ok());
+        assert_eq!(
+            game_match.check_turn(&player_black),
+            Err(ServerError::NotYourTurn)
+        );
+        assert_eq!(
+            game_match.check_turn(&Uuid::new_v4()),
+            Err(ServerError::NotAPlayer)
+        );
+
+        game_match.board_state =
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1".to_string();
+
+        assert_eq!(game_match.turn_player(), "black");
+        assert_eq!(game_match.player_to_move(), player_black);
+        assert_eq!(
+            game_match.check_turn(&player_white),
+            Err(ServerError::NotYourTurn)
+        );
+        assert!(game_match.check_turn(&player_black).is_ok());
+    }
+
+    #[test]
+    fn test_offers_and_takeback() {
+        let player_white = Uuid::new_v4();
+        let player_black = Uuid::new_v4();
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let after_e4 = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+        let mut game_match = GameMatch {
+            id: Uuid::new_v4(),
+            player_white,
+            player_black,
+            board_state: "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2"
+                .to_string(),
+            move_history: vec!["e4".to_string(), "e5".to_string()],
+            position_history: vec![start.to_string(), after_e4.to_string()],
+            clock: Clock::new(TimeControl::Unlimited, Instant::now()),
+            disconnects: HashMap::new(),
+            pending_offer: None,
+        };
+
+        assert_eq!(
+            game_match.make_offer(Uuid::new_v4(), Offer::Draw),
+            Err(ServerError::NotAPlayer)
+        );
+        assert!(game_match.make_offer(player_white, Offer::Draw).is_ok());
+        assert_eq!(
+            game_match.make_offer(player_black, Offer::Takeback),
+            Err(ServerError::OfferPending)
+        );
+        // only the opponent answers, and only the offer that was made
+        assert_eq!(
+            game_match.answer_offer(&player_white, Offer::Draw),
+            Err(ServerError::NoPendingOffer)
+        );
+        assert_eq!(
+            game_match.answer_offer(&player_black, Offer::Takeback),
+            Err(ServerError::NoPendingOffer)
+        );
+        assert!(game_match.answer_offer(&player_black, Offer::Draw).is_ok());
+        assert_eq!(game_match.pending_offer, None);
+
+        // black is not to move, so only its own reply to e4 is undone
+        assert_eq!(game_match.takeback_plies(&player_black), 1);
+        assert_eq!(game_match.takeback_plies(&player_white), 2);
+        game_match.take_back(1, Instant::now()).unwrap();
+        assert_eq!(game_match.board_state, after_e4);
+        assert_eq!(game_match.move_history, vec!["e4".to_string()]);
+        assert_eq!(game_match.turn_player(), "black");
+
+        // black has not moved anymore, it cannot ask for a takeback
+        assert_eq!(
+            game_match.make_offer(player_black, Offer::Takeback),
+            Err(ServerError::NothingToTakeBack)
+        );
+        assert!(game_match.make_offer(player_white, Offer::Takeback).is_ok());
+        let pending = game_match
+            .answer_offer(&player_black, Offer::Takeback)
+            .unwrap();
+        let plies = game_match.takeback_plies(&pending.from);
+        game_match.take_back(plies, Instant::now()).unwrap();
+        assert_eq!(game_match.board_state, start);
+        assert!(game_match.move_history.is_empty());
+        assert!(game_match.position_history.is_empty());
+        assert_eq!(
+            game_match.take_back(1, Instant::now()),
+            Err(ServerError::NothingToTakeBack)
+        );
+    }
+
+    #[test]
+    fn test_garbage_json_is_rejected() {
+        for text in [
+            "not json at all",
+            "{\"type\": ",
+            "{\"type\":\"Move\"}",
+            "[1, 2, 3]",
+        ] {
+            assert!(
+                matches!(
+                    parse_client_event(text),
+                    Err(ServerError::MalformedMessage(_))
+                ),
+                "{} should be rejected",
+                text
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unknown_event_type() {
+        let connections = new_connection_map();
+        let matches = new_match_map();
+        let waiting_queue = new_waiting_queue();
+
+        let client_data = parse_client_event(r#"{"type":"Teleport","to":"e8"}"#).unwrap();
+        assert!(matches!(client_data, Unknown));
+
+        let result = handle_event(
+            &mut Uuid::new_v4(),
+            client_data,
+            &connections,
+            &matches,
+            &waiting_queue,
+            &new_session_map(),
+        )
+        .await;
+        assert_eq!(result, Err(ServerError::UnknownEvent));
+    }
+
+    #[tokio::test]
+    async fn test_move_and_resign_without_match() {
+        let connections = new_connection_map();
+        let matches = new_match_map();
+        let waiting_queue = new_waiting_queue();
+        let sessions = new_session_map();
+        let mut player_id = Uuid::new_v4();
+
+        let events = [
+            r#"{"type":"Move","step":{"Quiet":{"piece_type":"WhitePawn","from_square":{"x":4,"y":1},"to_square":{"x":4,"y":3},"promotion_piece":null}}}"#,
+            r#"{"type":"Resign"}"#,
+            r#"{"type":"OfferDraw"}"#,
+            r#"{"type":"AcceptTakeback"}"#,
+            r#"{"type":"ClaimDraw"}"#,
+            r#"{"type":"Chat","text":"hello"}"#,
+        ];
+        for text in events {
+            let client_data = parse_client_event(text).unwrap();
+            let result = handle_event(
+                &mut player_id,
+                client_data,
+                &connections,
+                &matches,
+                &waiting_queue,
+                &sessions,
+            )
+            .await;
+            assert_eq!(result, Err(ServerError::NotInMatch));
+        }
+        assert!(matches.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_join_with_other_protocol_version() {
+        let connections = new_connection_map();
+        let matches = new_match_map();
+        let waiting_queue = new_waiting_queue();
+
+        let client_data = parse_client_event(r#"{"type":"Join","username":"old"}"#).unwrap();
+        let result = handle_event(
+            &mut Uuid::new_v4(),
+            client_data,
+            &connections,
+            &matches,
+            &waiting_queue,
+            &new_session_map(),
+        )
+        .await;
+        assert_eq!(result, Err(ServerError::IncompatibleVersion(0)));
+        assert_eq!(
+            ServerError::IncompatibleVersion(0).code(),
+            protocol::ErrorCode::IncompatibleVersion
+        );
+    }
+
+    #[tokio::test]
+    async fn test_join_with_unknown_session() {
+        let connections = new_connection_map();
+        let matches = new_match_map();
+        let waiting_queue = new_waiting_queue();
+        let sessions = new_session_map();
+        let mut player_id = Uuid::new_v4();
+        let original_id = player_id;
+
+        let client_data = ClientEvent::Join {
+            username: "returning".to_string(),
+            protocol_version: PROTOCOL_VERSION,
+            session_token: Some(Uuid::new_v4()),
+        };
+        let result = handle_event(
+            &mut player_id,
+            client_data,
+            &connections,
+            &matches,
+            &waiting_queue,
+            &sessions,
+        )
+        .await;
+        assert_eq!(result, Err(ServerError::InvalidSession));
+        assert_eq!(player_id, original_id);
+    }
+
+    async fn next_server_message(
+        ws: &mut WebSocketStream<tokio_tungstenite::MaybeTlsStream<TcpStream>>,
+    ) -> ServerMessage2 {
+        loop {
+            let message = ws.next().await.unwrap().unwrap();
+            if message.is_text() {
+                return serde_json::from_str(message.to_text().unwrap()).unwrap();
+            }
+        }
+    }
+
+    // serves websocket connections on a local port, returns its url
+    async fn spawn_test_server(
+        connections: &ConnectionMap,
+        matches: &MatchMap,
+        sessions: &SessionMap,
+    ) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let (connections, matches, sessions) =
+            (connections.clone(), matches.clone(), sessions.clone());
+        let waiting_queue = new_waiting_queue();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(handle_connection(
+                    stream,
+                    connections.clone(),
+                    matches.clone(),
+                    waiting_queue.clone(),
+                    sessions.clone(),
+                ));
+            }
+        });
+        url
+    }
+
+    fn join(username: &str, session_token: Option<Uuid>) -> Message {
+        let event = ClientEvent::Join {
+            username: username.to_string(),
+            protocol_version: PROTOCOL_VERSION,
+            session_token,
+        };
+        Message::Text(serde_json::to_string(&event).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_player_reattaches_to_its_match() {
+        let connections = new_connection_map();
+        let matches = new_match_map();
+        let sessions = new_session_map();
+        let url = spawn_test_server(&connections, &matches, &sessions).await;
+
+        let (mut ws, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+        ws.send(join("player", None)).await.unwrap();
+        let ServerMessage2::Session { token } = next_server_message(&mut ws).await else {
+            panic!("expected a session token");
+        };
+        assert!(matches!(
+            next_server_message(&mut ws).await,
+            ServerMessage2::Ok { .. }
+        ));
+
+        // put the player into a match with an opponent that is not connected
+        let player_id = sessions.lock().await[&token].player_id;
+        let match_id = Uuid::new_v4();
+        matches.lock().await.insert(
+            match_id,
+            GameMatch {
+                id: match_id,
+                player_white: player_id,
+                player_black: Uuid::new_v4(),
+                board_state: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string(),
+                move_history: Vec::new(),
+                position_history: Vec::new(),
+                clock: Clock::new(TimeControl::Unlimited, Instant::now()),
+                disconnects: HashMap::new(),
+                pending_offer: None,
+            },
+        );
+        connections
+            .lock()
+            .await
+            .get_mut(&player_id)
+            .unwrap()
+            .current_match = Some(match_id);
+
+        ws.close(None).await.unwrap();
+        while !matches.lock().await[&match_id]
+            .disconnects
+            .contains_key(&player_id)
+        {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(sessions.lock().await.contains_key(&token));
+
+        let (mut ws, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+        ws.send(join("player", Some(token))).await.unwrap();
+        assert!(matches!(
+            next_server_message(&mut ws).await,
+            ServerMessage2::Session { token: t } if t == token
+        ));
+        assert!(matches!(
+            next_server_message(&mut ws).await,
+            ServerMessage2::Ok { .. }
+        ));
+        assert!(matches!(
+            next_server_message(&mut ws).await,
+            ServerMessage2::MatchFound { match_id: id, color, .. } if id == match_id && color == "white"
+        ));
+        assert!(matches!(
+            next_server_message(&mut ws).await,
+            ServerMessage2::UIUpdate { .. }
+        ));
+
+        assert!(matches.lock().await[&match_id].disconnects.is_empty());
+        let conn_map = connections.lock().await;
+        assert_eq!(conn_map.len(), 1);
+        assert_eq!(conn_map[&player_id].current_match, Some(match_id));
+    }
+
+    #[tokio::test]
+    async fn test_username_is_fixed_for_the_session() {
+        let connections = new_connection_map();
+        let matches = new_match_map();
+        let sessions = new_session_map();
+        let url = spawn_test_server(&connections, &matches, &sessions).await;
+
+        let (mut ws, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+        ws.send(join("muted", None)).await.unwrap();
+        let ServerMessage2::Session { token } = next_server_message(&mut ws).await else {
+            panic!("expected a session token");
+        };
+        next_server_message(&mut ws).await;
+
+        // renaming on the same connection is refused
+        ws.send(join("other", None)).await.unwrap();
+        assert!(matches!(
+            next_server_message(&mut ws).await,
+            ServerMessage2::Error {
+                code: protocol::ErrorCode::AlreadyJoined,
+                ..
+            }
+        ));
+
+        // so is taking the name of somebody else
+        let (mut impostor, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+        impostor.send(join("muted", None)).await.unwrap();
+        assert!(matches!(
+            next_server_message(&mut impostor).await,
+            ServerMessage2::Error {
+                code: protocol::ErrorCode::UsernameTaken,
+                ..
+            }
+        ));
+
+        let player_id = sessions.lock().await[&token].player_id;
+        assert_eq!(
+            connections.lock().await[&player_id].username.as_deref(),
+            Some("muted")
+        );
+
+        // and a rejoin keeps the name of the session
+        ws.close(None).await.unwrap();
+        while sessions.lock().await.contains_key(&token) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        // the session outlives the connection only in a match, put it back for the rejoin
+        sessions.lock().await.insert(
+            token,
+            Session {
+                player_id,
+                username: "muted".to_string(),
+            },
+        );
+        let (mut ws, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+        ws.send(join("other", Some(token))).await.unwrap();
+        next_server_message(&mut ws).await;
+        next_server_message(&mut ws).await;
+        assert_eq!(
+            connections.lock().await[&player_id].username.as_deref(),
+            Some("muted")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_chat_needs_a_username() {
+        let connections = new_connection_map();
+        let matches = new_match_map();
+        let sessions = new_session_map();
+        let url = spawn_test_server(&connections, &matches, &sessions).await;
+
+        let (mut ws, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+        let player_id = loop {
+            if let Some(player_id) = connections.lock().await.keys().next() {
+                break *player_id;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+
+        // a match reached without a Join
+        let match_id = Uuid::new_v4();
+        matches.lock().await.insert(
+            match_id,
+            GameMatch {
+                id: match_id,
+                player_white: player_id,
+                player_black: Uuid::new_v4(),
+                board_state: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string(),
+                move_history: Vec::new(),
+                position_history: Vec::new(),
+                clock: Clock::new(TimeControl::Unlimited, Instant::now()),
+                disconnects: HashMap::new(),
+                pending_offer: None,
+            },
+        );
+        connections
+            .lock()
+            .await
+            .get_mut(&player_id)
+            .unwrap()
+            .current_match = Some(match_id);
+
+        let chat = ClientEvent::Chat {
+            text: "hello".to_string(),
+        };
+        ws.send(Message::Text(serde_json::to_string(&chat).unwrap()))
+            .await
+            .unwrap();
+        assert!(matches!(
+            next_server_message(&mut ws).await,
+            ServerMessage2::Error {
+                code: protocol::ErrorCode::NotJoined,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_error_message_serialization() {
+        let message = ServerMessage2::from(ServerError::NotYourTurn);
+        let json = serde_json::to_string(&message).unwrap();
+        assert_eq!(
+            json,
+            r#"{"Error":{"code":"NotYourTurn","message":"it is not your turn"}}"#
+        );
+    }
+
     #[tokio::test]
     async fn test_broadcast_to_empty_connections() {
         let connections = new_connection_map();
@@ -449,15 +1498,26 @@ mod tests {
         let connections = new_connection_map();
         let matches = new_match_map();
         let waiting_queue = new_waiting_queue();
+        let sessions = new_session_map();
 
         let player_id = Uuid::new_v4();
 
         {
             waiting_queue.lock().await.push_back(player_id);
             assert_eq!(waiting_queue.lock().await.len(), 1);
+            sessions.lock().await.insert(
+                Uuid::new_v4(),
+                Session {
+                    player_id,
+                    username: "player".to_string(),
+                },
+            );
         }
 
-        cleanup_player(player_id, &connections, &matches, &waiting_queue).await;
+        cleanup_player(player_id, &connections, &matches, &waiting_queue, &sessions).await;
+
+        // nothing to come back to outside of a match
+        assert!(sessions.lock().await.is_empty());
 
         {
             let queue = waiting_queue.lock().await;
@@ -1,5 +1,9 @@
+mod chat;
+mod clock;
 mod connection;
 mod matchmaking;
+mod matchwatcher;
+mod servererror;
 use env_logger::Env;
 use log::{error, info};
 use tokio::net::TcpListener;
@@ -17,6 +21,7 @@ async fn main() -> anyhow::Result<()> {
     let connections = connection::new_connection_map();
     let matches = connection::new_match_map();
     let waiting_queue = connection::new_waiting_queue();
+    let sessions = connection::new_session_map();
 
     // Start matchmaking background task
     let matchmaker = matchmaking::MatchmakingSystem::new(
@@ -28,15 +33,24 @@ async fn main() -> anyhow::Result<()> {
         matchmaker.run().await;
     });
 
+    // End games whose clock ran out or whose player did not come back
+    let match_watcher =
+        matchwatcher::MatchWatcher::new(connections.clone(), matches.clone(), sessions.clone());
+    tokio::spawn(async move {
+        match_watcher.run().await;
+    });
+
     // Main connection loop
     while let Ok((stream, _)) = listener.accept().await {
         let connections = connections.clone();
         let matches = matches.clone();
         let waiting_queue = waiting_queue.clone();
+        let sessions = sessions.clone();
 
         tokio::spawn(async move {
             if let Err(e) =
-                connection::handle_connection(stream, connections, matches, waiting_queue).await
+                connection::handle_connection(stream, connections, matches, waiting_queue, sessions)
+                    .await
             {
                 error!("Connection error: {}", e);
             }
@@ -1,7 +1,10 @@
-use crate::connection::ServerMessage2;
+use crate::clock::Clock;
 use crate::connection::{ConnectionMap, GameMatch, MatchMap, WaitingQueue};
 use log::{error, info, warn};
+use protocol::{ServerMessage2, TimeControl};
 use rand::random;
+use std::collections::{HashMap, VecDeque};
+use std::time::Instant;
 use uuid::Uuid;
 
 pub struct MatchmakingSystem {
@@ -30,9 +33,22 @@ impl MatchmakingSystem {
         //info!("Checking for new matches!");
         let mut queue = self.waiting_queue.lock().await;
 
-        while queue.len() >= 2 {
-            let player1 = queue.pop_front().unwrap();
-            let player2 = queue.pop_front().unwrap();
+        loop {
+            let (first, second, time_control) = {
+                let conn_map = self.connections.lock().await;
+                let time_control = |id: &Uuid| {
+                    conn_map
+                        .get(id)
+                        .map(|player| player.time_control)
+                        .unwrap_or_default()
+                };
+                match find_pair(&queue, time_control) {
+                    Some((first, second)) => (first, second, time_control(&queue[first])),
+                    None => break,
+                }
+            };
+            let player2 = queue.remove(second).unwrap();
+            let player1 = queue.remove(first).unwrap();
 
             info!("Creating new match. Players: {}, {}", &player1, &player2);
 
@@ -51,6 +67,10 @@ impl MatchmakingSystem {
                 player_black: black_player,
                 board_state: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string(),
                 move_history: Vec::new(),
+                position_history: Vec::new(),
+                clock: Clock::new(time_control, Instant::now()),
+                disconnects: HashMap::new(),
+                pending_offer: None,
             };
 
             info!("Match id: {}", &game_match.id);
@@ -129,6 +149,21 @@ impl MatchmakingSystem {
     }
 }
 
+// the two players waiting the longest who asked for the same time control
+fn find_pair(
+    queue: &VecDeque<Uuid>,
+    time_control: impl Fn(&Uuid) -> TimeControl,
+) -> Option<(usize, usize)> {
+    for first in 0..queue.len() {
+        for second in first + 1..queue.len() {
+            if time_control(&queue[first]) == time_control(&queue[second]) {
+                return Some((first, second));
+            }
+        }
+    }
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -179,6 +214,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_only_same_time_controls_are_paired() {
+        let blitz = TimeControl::Fischer {
+            base_ms: 180_000,
+            increment_ms: 2_000,
+        };
+        let players: Vec<Uuid> = (0..4).map(|_| Uuid::new_v4()).collect();
+        let controls = [
+            TimeControl::Unlimited,
+            blitz,
+            TimeControl::Correspondence { days_per_move: 1 },
+            blitz,
+        ];
+        let time_control = |id: &Uuid| controls[players.iter().position(|p| p == id).unwrap()];
+
+        let queue: VecDeque<Uuid> = players.iter().copied().collect();
+        assert_eq!(find_pair(&queue, time_control), Some((1, 3)));
+
+        let queue: VecDeque<Uuid> = players[..3].iter().copied().collect();
+        assert_eq!(find_pair(&queue, time_control), None);
+    }
+
     #[tokio::test]
     async fn test_matchmaking_with_odd_players() {
         let connections = new_connection_map();
@@ -0,0 +1,220 @@
+use crate::connection::{ConnectionMap, MatchMap, SessionMap, broadcast_to_match, clean_up_match};
+use log::warn;
+use protocol::ServerMessage2;
+use std::collections::HashSet;
+use std::time::Instant;
+use uuid::Uuid;
+
+// ends the games in which the player to move ran out of time or a player did not come back in time
+pub struct MatchWatcher {
+    connections: ConnectionMap,
+    matches: MatchMap,
+    sessions: SessionMap,
+}
+
+impl MatchWatcher {
+    pub fn new(connections: ConnectionMap, matches: MatchMap, sessions: SessionMap) -> Self {
+        Self {
+            connections,
+            matches,
+            sessions,
+        }
+    }
+
+    pub async fn run(&self) {
+        loop {
+            self.check_matches().await;
+            self.drop_stale_sessions().await;
+            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        }
+    }
+
+    async fn check_matches(&self) {
+        let now = Instant::now();
+        let finished: Vec<(Uuid, ServerMessage2)> = {
+            let matches = self.matches.lock().await;
+            matches
+                .values()
+                .filter_map(|game_match| {
+                    let winner = if game_match.clock.is_flagged(now) {
+                        game_match.time_out_result()
+                    } else {
+                        game_match.abandon_result(now)?
+                    };
+                    Some((game_match.id, ServerMessage2::GameEnd { winner }))
+                })
+                .collect()
+        };
+
+        for (match_id, message) in finished {
+            warn!("Match ended by the watcher: {}", &match_id);
+            let _ = broadcast_to_match(
+                &self.connections,
+                &self.matches,
+                match_id,
+                &serde_json::to_string(&message).unwrap(),
+            )
+            .await;
+            clean_up_match(&self.matches, &match_id).await;
+        }
+    }
+
+    // sessions of players who are neither connected nor waiting to come back to a match
+    async fn drop_stale_sessions(&self) {
+        // held throughout, so no Join can hand out a session between the snapshots and the cleanup
+        let mut sessions = self.sessions.lock().await;
+        let mut live_players: HashSet<Uuid> =
+            self.connections.lock().await.keys().copied().collect();
+        for game_match in self.matches.lock().await.values() {
+            live_players.extend(game_match.disconnects.keys().copied());
+        }
+        sessions.retain(|_, session| live_players.contains(&session.player_id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::Clock;
+    use crate::connection::{
+        GameMatch, Session, new_connection_map, new_match_map, new_session_map,
+    };
+    use engine::gameend::GameEnd;
+    use protocol::TimeControl;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn timed_match(board_state: &str, started: Instant) -> GameMatch {
+        GameMatch {
+            id: Uuid::new_v4(),
+            player_white: Uuid::new_v4(),
+            player_black: Uuid::new_v4(),
+            board_state: board_state.to_string(),
+            move_history: Vec::new(),
+            position_history: Vec::new(),
+            clock: Clock::new(
+                TimeControl::Fischer {
+                    base_ms: 1_000,
+                    increment_ms: 0,
+                },
+                started,
+            ),
+            disconnects: HashMap::new(),
+            pending_offer: None,
+        }
+    }
+
+    #[test]
+    fn test_time_out_result() {
+        let started = Instant::now();
+        let game_match = timed_match(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            started,
+        );
+        assert_eq!(
+            game_match.time_out_result(),
+            GameEnd::BlackWon("Time".to_string())
+        );
+
+        // black has nothing left to mate with
+        let game_match = timed_match("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", started);
+        assert_eq!(
+            game_match.time_out_result(),
+            GameEnd::Draw("Timeout vs insufficient material".to_string())
+        );
+
+        // a lone knight could still mate with the help of the rook
+        let game_match = timed_match("4kr2/8/8/8/8/8/8/3NK3 b - - 0 1", started);
+        assert_eq!(
+            game_match.time_out_result(),
+            GameEnd::WhiteWon("Time".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_flagged_matches_are_ended() {
+        let connections = new_connection_map();
+        let matches = new_match_map();
+        let watcher = MatchWatcher::new(connections.clone(), matches.clone(), new_session_map());
+
+        let flagged = timed_match(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            Instant::now() - Duration::from_secs(2),
+        );
+        let running = timed_match(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            Instant::now(),
+        );
+        let running_id = running.id;
+        {
+            let mut matches = matches.lock().await;
+            matches.insert(flagged.id, flagged);
+            matches.insert(running.id, running);
+        }
+
+        watcher.check_matches().await;
+
+        let matches = matches.lock().await;
+        assert_eq!(matches.len(), 1);
+        assert!(matches.contains_key(&running_id));
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_matches_are_ended() {
+        let connections = new_connection_map();
+        let matches = new_match_map();
+        let sessions = new_session_map();
+        let watcher = MatchWatcher::new(connections.clone(), matches.clone(), sessions.clone());
+
+        let now = Instant::now();
+        let mut abandoned = timed_match("4k3/8/8/8/8/8/8/3QK3 w - - 0 1", now);
+        let mut waiting = timed_match("4k3/8/8/8/8/8/8/3QK3 w - - 0 1", now);
+        abandoned.clock = Clock::new(TimeControl::Unlimited, now);
+        waiting.clock = Clock::new(TimeControl::Unlimited, now);
+        abandoned
+            .disconnects
+            .insert(abandoned.player_black, now - Duration::from_secs(1));
+        waiting
+            .disconnects
+            .insert(waiting.player_white, now + Duration::from_secs(60));
+
+        assert_eq!(
+            abandoned.abandon_result(now),
+            Some(GameEnd::WhiteWon("Abandoned".to_string()))
+        );
+        assert_eq!(waiting.abandon_result(now), None);
+
+        let (abandoned_black, waiting_white) = (abandoned.player_black, waiting.player_white);
+        {
+            let mut sessions = sessions.lock().await;
+            for (player_id, username) in [(abandoned_black, "gone"), (waiting_white, "waiting")] {
+                let session = Session {
+                    player_id,
+                    username: username.to_string(),
+                };
+                sessions.insert(Uuid::new_v4(), session);
+            }
+        }
+        let waiting_id = waiting.id;
+        {
+            let mut matches = matches.lock().await;
+            matches.insert(abandoned.id, abandoned);
+            matches.insert(waiting.id, waiting);
+        }
+
+        watcher.check_matches().await;
+        watcher.drop_stale_sessions().await;
+
+        let matches = matches.lock().await;
+        assert_eq!(matches.len(), 1);
+        assert!(matches.contains_key(&waiting_id));
+
+        let sessions = sessions.lock().await;
+        assert_eq!(sessions.len(), 1);
+        assert!(
+            sessions
+                .values()
+                .all(|session| session.player_id == waiting_white)
+        );
+    }
+}
@@ -0,0 +1,102 @@
+use engine::fenerror::FenError;
+use engine::illegalmove::IllegalMove;
+use protocol::{ErrorCode, PROTOCOL_VERSION};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServerError {
+    MalformedMessage(String),
+    UnknownEvent,
+    IncompatibleVersion(u32),
+    InvalidSession,
+    NotJoined,
+    AlreadyJoined,
+    UsernameTaken,
+    NotInMatch,
+    NotAPlayer,
+    NotYourTurn,
+    OutOfTime,
+    IllegalMove(IllegalMove),
+    InvalidFen(FenError),
+    OfferPending,
+    NoPendingOffer,
+    NothingToTakeBack,
+    NoDrawToClaim,
+    InvalidChatMessage(String),
+    RateLimited,
+    Muted,
+}
+
+impl ServerError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ServerError::MalformedMessage(_) => ErrorCode::MalformedMessage,
+            ServerError::UnknownEvent => ErrorCode::UnknownEvent,
+            ServerError::IncompatibleVersion(_) => ErrorCode::IncompatibleVersion,
+            ServerError::InvalidSession => ErrorCode::InvalidSession,
+            ServerError::NotJoined => ErrorCode::NotJoined,
+            ServerError::AlreadyJoined => ErrorCode::AlreadyJoined,
+            ServerError::UsernameTaken => ErrorCode::UsernameTaken,
+            ServerError::NotInMatch => ErrorCode::NotInMatch,
+            ServerError::NotAPlayer => ErrorCode::NotAPlayer,
+            ServerError::NotYourTurn => ErrorCode::NotYourTurn,
+            ServerError::OutOfTime => ErrorCode::OutOfTime,
+            ServerError::IllegalMove(_) => ErrorCode::IllegalMove,
+            ServerError::InvalidFen(_) => ErrorCode::InvalidFen,
+            ServerError::OfferPending => ErrorCode::OfferPending,
+            ServerError::NoPendingOffer => ErrorCode::NoPendingOffer,
+            ServerError::NothingToTakeBack => ErrorCode::NothingToTakeBack,
+            ServerError::NoDrawToClaim => ErrorCode::NoDrawToClaim,
+            ServerError::InvalidChatMessage(_) => ErrorCode::InvalidChatMessage,
+            ServerError::RateLimited => ErrorCode::RateLimited,
+            ServerError::Muted => ErrorCode::Muted,
+        }
+    }
+}
+
+impl fmt::Display for ServerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerError::MalformedMessage(e) => write!(f, "malformed message: {}", e),
+            ServerError::UnknownEvent => write!(f, "unknown client event"),
+            ServerError::IncompatibleVersion(version) => write!(
+                f,
+                "client speaks protocol version {}, the server speaks {}",
+                version, PROTOCOL_VERSION
+            ),
+            ServerError::InvalidSession => {
+                write!(f, "the session token is unknown or still in use")
+            }
+            ServerError::NotJoined => write!(f, "you have not joined yet"),
+            ServerError::AlreadyJoined => write!(f, "you have already joined"),
+            ServerError::UsernameTaken => write!(f, "the username is already in use"),
+            ServerError::NotInMatch => write!(f, "you are not in a match"),
+            ServerError::NotAPlayer => write!(f, "you are not a player of this match"),
+            ServerError::NotYourTurn => write!(f, "it is not your turn"),
+            ServerError::OutOfTime => write!(f, "your time is up"),
+            ServerError::IllegalMove(e) => write!(f, "{}", e),
+            ServerError::InvalidFen(e) => write!(f, "invalid fen: {}", e),
+            ServerError::OfferPending => write!(f, "an offer is already waiting for an answer"),
+            ServerError::NoPendingOffer => write!(f, "there is no such offer to answer"),
+            ServerError::NothingToTakeBack => write!(f, "you have no move to take back"),
+            ServerError::NoDrawToClaim => write!(f, "there is no draw to claim"),
+            ServerError::InvalidChatMessage(e) => write!(f, "invalid chat message: {}", e),
+            ServerError::RateLimited => write!(f, "you are sending messages too fast"),
+            ServerError::Muted => write!(f, "you are muted"),
+        }
+    }
+}
+
+impl std::error::Error for ServerError {}
+
+impl From<IllegalMove> for ServerError {
+    fn from(e: IllegalMove) -> Self {
+        ServerError::IllegalMove(e)
+    }
+}
+
+impl From<FenError> for ServerError {
+    fn from(e: FenError) -> Self {
+        ServerError::InvalidFen(e)
+    }
+}
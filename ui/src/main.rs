@@ -5,7 +5,7 @@ use engine::{boardsquare::BoardSquare, chessmove::ChessMove};
 use env_logger::Env;
 use futures_util::{SinkExt, StreamExt};
 use log::{error, info, warn};
-use serde::{Deserialize, Serialize};
+use protocol::{ClientEvent, ClockTimes, PROTOCOL_VERSION, ServerMessage2, TimeControl};
 use std::path::Path;
 use std::process::Command;
 use std::sync::{Arc, Mutex};
@@ -48,50 +48,6 @@ async fn main() -> anyhow::Result<(), eframe::Error> {
     )
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub enum ServerMessage2 {
-    GameEnd {
-        winner: GameEnd,
-    },
-    UIUpdate {
-        fen: String,
-        turn_player: String,
-        move_history: Vec<String>,
-    },
-    MatchFound {
-        match_id: Uuid,
-        color: String,
-        opponent_name: String,
-    },
-    LegalMoves {
-        moves: Vec<ChessMove>,
-    },
-    Ok {
-        response: Result<(), String>,
-    },
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(tag = "type")]
-pub enum ClientEvent {
-    Join {
-        username: String,
-    },
-    FindMatch,
-    Move {
-        step: ChessMove,
-        turn_player: String,
-    },
-    Resign,
-    Chat {
-        text: String,
-    },
-    RequestLegalMoves {
-        fen: String,
-    },
-    CloseConnection,
-}
-
 // Game state
 #[derive(Debug, Clone)]
 struct GameState {
@@ -103,12 +59,14 @@ struct GameState {
     available_moves: Option<Vec<ChessMove>>,
     turn_player: Option<String>,
     move_history: Vec <String>,
+    clock: Option<ClockTimes>,
 }
 
 impl Default for GameState {
     fn default() -> Self {
-        let cuccfck: Vec<ChessMove> =
-            engine::get_available_moves("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let cuccfck: Option<Vec<ChessMove>> =
+            engine::get_available_moves("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")
+                .ok();
 
         Self {
             fen: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string(),
@@ -116,9 +74,10 @@ impl Default for GameState {
             opponent_name: None,
             match_id: None,
             game_over: None,
-            available_moves: Some(cuccfck),
+            available_moves: cuccfck,
             turn_player: Some("white".to_string()),
             move_history: Vec::new(),
+            clock: None,
         }
     }
 }
@@ -247,14 +206,20 @@ impl ChessApp {
         let (mut write, mut read) = ws_stream.split();
 
         // Send initial join message and immediately send FindMatch
-        let join_event = ClientEvent::Join { username };
+        let join_event = ClientEvent::Join {
+            username,
+            protocol_version: PROTOCOL_VERSION,
+            session_token: None,
+        };
         write
             .send(Message::Text(serde_json::to_string(&join_event)?))
             .await?;
         info!("Sent Join event");
 
         // Send FindMatch immediately after joining
-        let find_match_event = ClientEvent::FindMatch;
+        let find_match_event = ClientEvent::FindMatch {
+            time_control: TimeControl::default(),
+        };
         write
             .send(Message::Text(serde_json::to_string(&find_match_event)?))
             .await?;
@@ -274,8 +239,9 @@ impl ChessApp {
                             // Update game state
                             if let Ok(mut state) = game_state_clone.lock() {
                                 match &server_msg {
-                                    ServerMessage2::UIUpdate { fen, turn_player, move_history } => {
+                                    ServerMessage2::UIUpdate { fen, turn_player, move_history, clock } => {
                                         info!("raw fen: {}", &fen);
+                                        state.clock = *clock;
                                         state.fen = fen.clone();
                                         state.turn_player = Some(turn_player.clone());
                                         warn!("turn player: {}", &state.turn_player.clone().unwrap());
@@ -430,11 +396,6 @@ impl ChessApp {
 
                 let move_event = ClientEvent::Move {
                     step: unwrapped_move,
-                    turn_player: if player_color == Some("white".to_string()) {
-                        "black".to_string()
-                    } else {
-                        "white".to_string()
-                    },
                 };
 
                 let _ = tx.send(move_event);
@@ -512,7 +473,22 @@ impl ChessApp {
                             self.state = AppState::FindingMatch;
                         }
                     }
-                    ServerMessage2::UIUpdate { fen, turn_player, move_history } => {
+                    ServerMessage2::Error { code, message } => {
+                        warn!("Server error {}: {}", code, message);
+                    }
+                    ServerMessage2::OpponentDisconnected { timeout_ms } => {
+                        warn!("Opponent disconnected, {} ms to reconnect", timeout_ms);
+                    }
+                    ServerMessage2::OpponentReconnected => {
+                        info!("Opponent reconnected");
+                    }
+                    ServerMessage2::OfferMade { offer, color } => {
+                        info!("{:?} offered by {}", offer, color);
+                    }
+                    ServerMessage2::ChatMessage { from, text, .. } => {
+                        info!("{}: {}", from, text);
+                    }
+                    ServerMessage2::UIUpdate { fen, .. } => {
                         if let Some(tx) = &self.tx_to_network {
                             let _ = tx.send(ClientEvent::RequestLegalMoves {fen: self.game_state.lock().unwrap().fen.clone()});
                         }
@@ -1038,6 +1014,13 @@ impl eframe::App for ChessApp {
                                 
                                 ui.vertical_centered(|ui| {
                                     ui.heading(egui::RichText::new("Move History").color(text_color));
+                                    if let Some(clock) = self.game_state.lock().unwrap().clock {
+                                        ui.label(egui::RichText::new(format!(
+                                            "White {}  Black {}",
+                                            format_clock(clock.white_ms),
+                                            format_clock(clock.black_ms)
+                                        )).color(text_color));
+                                    }
                                     ui.separator();
                                     
                                     // Scroll area for move history
@@ -1129,6 +1112,12 @@ impl eframe::App for ChessApp {
         ctx.request_repaint();
     }
 }
+// remaining time as m:ss
+fn format_clock(ms: u64) -> String {
+    let seconds = ms / 1000;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1199,13 +1188,17 @@ mod tests {
         // Test Join event
         let join_event = ClientEvent::Join {
             username: "test".to_string(),
+            protocol_version: PROTOCOL_VERSION,
+            session_token: None,
         };
         let serialized = serde_json::to_string(&join_event).unwrap();
         assert!(serialized.contains("Join"));
         assert!(serialized.contains("test"));
 
         // Test FindMatch event
-        let find_match_event = ClientEvent::FindMatch;
+        let find_match_event = ClientEvent::FindMatch {
+            time_control: TimeControl::default(),
+        };
         let serialized = serde_json::to_string(&find_match_event).unwrap();
         assert!(serialized.contains("FindMatch"));
 
@@ -1216,10 +1209,7 @@ mod tests {
             to_square: BoardSquare { x: 2, y: 2 },
             promotion_piece: None,
         };
-        let move_event = ClientEvent::Move { 
-            step: chess_move, 
-            turn_player: "white".to_string() 
-        };
+        let move_event = ClientEvent::Move { step: chess_move };
         let serialized = serde_json::to_string(&move_event).unwrap();
         assert!(serialized.contains("Move"));
 
@@ -1258,11 +1248,12 @@ mod tests {
 
         // Test UIUpdate message
         let ui_update_json =
-            r#"{"UIUpdate":{"fen":"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1","turn_player":"white"}}"#;
+            r#"{"UIUpdate":{"fen":"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1","turn_player":"white","move_history":[]}}"#;
         let message: ServerMessage2 = serde_json::from_str(ui_update_json).unwrap();
         match message {
-            ServerMessage2::UIUpdate { fen, turn_player, move_history } => {
+            ServerMessage2::UIUpdate { fen, turn_player, move_history, clock } => {
                 assert!(fen.contains("rnbqkbnr"));
+                assert!(clock.is_none());
                 assert_eq!(turn_player, "white");
             }
             _ => panic!("Expected UIUpdate message"),